@@ -0,0 +1,449 @@
+//! Module for world domination campaigns
+use std::fmt::{self, Display};
+
+use crate::{Gadget, Henchman, Supervillain, location::Location, supervillain::EvilError};
+
+/// Stages of world domination, in the order they are carried out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Stage {
+    FindTargets,
+    BuildHeadquarters,
+    FightEnemies,
+    DoHardThings,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [
+        Stage::FindTargets,
+        Stage::BuildHeadquarters,
+        Stage::FightEnemies,
+        Stage::DoHardThings,
+    ];
+
+    /// Returns the stage that follows this one, if any.
+    #[must_use]
+    pub fn next(self) -> Option<Stage> {
+        match self {
+            Stage::FindTargets => Some(Stage::BuildHeadquarters),
+            Stage::BuildHeadquarters => Some(Stage::FightEnemies),
+            Stage::FightEnemies => Some(Stage::DoHardThings),
+            Stage::DoHardThings => None,
+        }
+    }
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stage = match self {
+            Stage::FindTargets => "find targets",
+            Stage::BuildHeadquarters => "build headquarters",
+            Stage::FightEnemies => "fight enemies",
+            Stage::DoHardThings => "do hard things",
+        };
+        f.write_str(stage)
+    }
+}
+
+/// Result of running a stage once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StageStatus {
+    Completed { details: String },
+    Failed { reason: String },
+}
+
+/// What happened when the first stage of world domination was started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StageOutcome {
+    /// The secret headquarters were built in the weak target chosen by the target selector.
+    HeadquartersBuilt { location: String },
+    /// There is no sidekick to find the targets.
+    NoSidekick,
+    /// The sidekick didn't find any weak target.
+    NoTargets,
+    /// The henchman refused to build the headquarters.
+    HenchmanRefused { reason: String },
+}
+
+impl Display for StageOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StageOutcome::HeadquartersBuilt { location } => {
+                write!(f, "Headquarters built in {location}")
+            }
+            StageOutcome::NoSidekick => f.write_str("No sidekick"),
+            StageOutcome::NoTargets => f.write_str("No weak targets"),
+            StageOutcome::HenchmanRefused { reason } => write!(f, "Henchman refused: {reason}"),
+        }
+    }
+}
+
+/// Record of a stage that was run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StageRecord {
+    pub stage: Stage,
+    pub status: StageStatus,
+}
+
+/// Campaign of world domination that runs its stages in order.
+///
+/// Every stage that is run is recorded, whether it succeeds or not. When a stage fails the
+/// campaign stops there, and running it again resumes from the failed stage. The stages are
+/// carried out by the stage methods of the supervillain.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Campaign {
+    next_stage: Option<Stage>,
+    targets: Vec<Location>,
+    headquarters: Option<String>,
+    records: Vec<StageRecord>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    retries: usize,
+}
+
+impl Default for Campaign {
+    fn default() -> Self {
+        Campaign {
+            next_stage: Some(Stage::FindTargets),
+            targets: vec![],
+            headquarters: None,
+            records: vec![],
+            retries: 0,
+        }
+    }
+}
+
+impl Campaign {
+    #[must_use]
+    pub fn new() -> Self {
+        Campaign::default()
+    }
+
+    /// Has the henchman try the tasks of stage 2 up to `retries` more times before the stage
+    /// fails.
+    #[must_use]
+    pub fn with_retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Returns the stage that will be run next, or `None` if the campaign is complete.
+    #[must_use]
+    pub fn next_stage(&self) -> Option<Stage> {
+        self.next_stage
+    }
+
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.next_stage.is_none()
+    }
+
+    /// Returns the weak targets found by the sidekick.
+    #[must_use]
+    pub fn targets(&self) -> &[Location] {
+        &self.targets
+    }
+
+    /// Returns the location of the secret headquarters once it has been built.
+    #[must_use]
+    pub fn headquarters(&self) -> Option<&str> {
+        self.headquarters.as_deref()
+    }
+
+    /// Returns the records of every stage run, in order, including failed attempts.
+    #[must_use]
+    pub fn records(&self) -> &[StageRecord] {
+        &self.records
+    }
+
+    /// Runs the pending stages in order until the campaign is complete or a stage fails.
+    ///
+    /// # Errors
+    /// - `EvilError::StageFailed` with the stage that failed and why. The campaign can be run
+    ///   again to retry it.
+    pub fn run<H: Henchman, G: Gadget>(
+        &mut self,
+        supervillain: &Supervillain,
+        henchman: &mut H,
+        gadget: &G,
+    ) -> Result<(), EvilError> {
+        while let Some(stage) = self.next_stage {
+            let status = match self.run_stage(stage, supervillain, henchman, gadget) {
+                Ok(details) => StageStatus::Completed { details },
+                Err(reason) => StageStatus::Failed { reason },
+            };
+            self.records.push(StageRecord {
+                stage,
+                status: status.clone(),
+            });
+            if let StageStatus::Failed { reason } = status {
+                return Err(EvilError::StageFailed {
+                    stage: stage.to_string(),
+                    reason,
+                });
+            }
+            self.next_stage = stage.next();
+        }
+        Ok(())
+    }
+
+    fn run_stage<H: Henchman, G: Gadget>(
+        &mut self,
+        stage: Stage,
+        supervillain: &Supervillain,
+        henchman: &mut H,
+        gadget: &G,
+    ) -> Result<String, String> {
+        match stage {
+            Stage::FindTargets => {
+                self.targets = supervillain
+                    .find_weak_targets(gadget)
+                    .map_err(|outcome| outcome.to_string())?;
+                Ok(format!("Found {} weak targets", self.targets.len()))
+            }
+            Stage::BuildHeadquarters => match supervillain.build_headquarters(
+                henchman,
+                &self.targets,
+                supervillain.target_selector(),
+            ) {
+                StageOutcome::HeadquartersBuilt { location } => {
+                    self.headquarters = Some(location.clone());
+                    Ok(StageOutcome::HeadquartersBuilt { location }.to_string())
+                }
+                outcome => Err(outcome.to_string()),
+            },
+            Stage::FightEnemies => supervillain
+                .fight_enemies_with_retries(henchman, self.retries)
+                .map(|_| String::from("Enemies fought"))
+                .map_err(|err| err.to_string()),
+            Stage::DoHardThings => supervillain
+                .do_hard_things_with_retries(henchman, self.retries)
+                .map(|_| String::from("Hard things done"))
+                .map_err(|err| err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertables::{assert_matches, assert_ok};
+    use mockall::{Sequence, predicate::eq};
+
+    use super::*;
+    use crate::{
+        gadget::MockGadget,
+        henchman::{BasicHenchman, HenchmanError, HenchmanKind, MockHenchman, Task},
+        sidekick::MockSidekick,
+        test_common,
+    };
+
+    fn supervillain_with_targets(targets: Vec<Location>) -> Supervillain<'static> {
+        let mut sidekick = MockSidekick::new();
+        sidekick
+            .expect_get_weak_targets()
+            .returning(move |_| targets.clone());
+        Supervillain {
+            sidekick: Some(sidekick),
+            ..Supervillain::default()
+        }
+    }
+
+    fn obedient_henchman() -> MockHenchman {
+        let mut henchman = MockHenchman::new();
+        henchman
+            .expect_build_secret_hq()
+            .returning(|location| Ok(test_common::outcome(Task::BuildSecretHq { location })));
+        henchman
+            .expect_fight_enemies()
+            .returning(|| Ok(test_common::outcome(Task::FightEnemies)));
+        henchman
+            .expect_do_hard_things()
+            .returning(|| Ok(test_common::outcome(Task::DoHardThings)));
+        henchman
+    }
+
+    #[test]
+    fn stage_outcomes_are_displayed_for_humans() {
+        assert_eq!(
+            StageOutcome::HeadquartersBuilt {
+                location: String::from("Tampa")
+            }
+            .to_string(),
+            "Headquarters built in Tampa"
+        );
+        assert_eq!(
+            StageOutcome::HenchmanRefused {
+                reason: String::from("Too tired")
+            }
+            .to_string(),
+            "Henchman refused: Too tired"
+        );
+    }
+
+    #[test]
+    fn new_campaign_starts_finding_targets() {
+        let campaign = Campaign::new();
+
+        assert_eq!(campaign.next_stage(), Some(Stage::FindTargets));
+        assert!(campaign.records().is_empty());
+    }
+
+    #[test]
+    fn campaign_runs_all_stages_in_order() {
+        let supervillain =
+            supervillain_with_targets(test_common::TARGETS.map(Location::from).to_vec());
+        let mut henchman = MockHenchman::new();
+        let mut sequence = Sequence::new();
+        henchman
+            .expect_build_secret_hq()
+            .with(eq(String::from(test_common::FIRST_TARGET)))
+            .once()
+            .in_sequence(&mut sequence)
+            .returning(|location| Ok(test_common::outcome(Task::BuildSecretHq { location })));
+        henchman
+            .expect_fight_enemies()
+            .once()
+            .in_sequence(&mut sequence)
+            .returning(|| Ok(test_common::outcome(Task::FightEnemies)));
+        henchman
+            .expect_do_hard_things()
+            .once()
+            .in_sequence(&mut sequence)
+            .returning(|| Ok(test_common::outcome(Task::DoHardThings)));
+        let mut campaign = Campaign::new();
+
+        assert_ok!(campaign.run(&supervillain, &mut henchman, &MockGadget::new()));
+
+        assert!(campaign.is_complete());
+        assert_eq!(campaign.headquarters(), Some(test_common::FIRST_TARGET));
+        assert_eq!(
+            campaign
+                .records()
+                .iter()
+                .map(|record| record.stage)
+                .collect::<Vec<_>>(),
+            Stage::ALL
+        );
+    }
+
+    #[test]
+    fn campaign_without_sidekick_fails_finding_targets() {
+        let supervillain = Supervillain::default();
+        let mut campaign = Campaign::new();
+
+        let result = campaign.run(&supervillain, &mut MockHenchman::new(), &MockGadget::new());
+
+        assert_matches!(result, Err(EvilError::StageFailed { stage, reason }) if stage == "find targets" && reason == "No sidekick");
+        assert_eq!(campaign.next_stage(), Some(Stage::FindTargets));
+        assert_eq!(
+            campaign.records(),
+            [StageRecord {
+                stage: Stage::FindTargets,
+                status: StageStatus::Failed {
+                    reason: String::from("No sidekick")
+                }
+            }]
+        );
+    }
+
+    #[test]
+    fn failed_campaign_resumes_from_failed_stage() {
+        let mut campaign = Campaign::new();
+        let _ = campaign.run(
+            &supervillain_with_targets(vec![]),
+            &mut MockHenchman::new(),
+            &MockGadget::new(),
+        );
+        let supervillain =
+            supervillain_with_targets(test_common::TARGETS.map(Location::from).to_vec());
+
+        assert_ok!(campaign.run(&supervillain, &mut obedient_henchman(), &MockGadget::new()));
+
+        assert!(campaign.is_complete());
+        assert_eq!(campaign.records().len(), 5);
+        assert_matches!(
+            &campaign.records()[0].status,
+            StageStatus::Failed { reason } if reason == "No weak targets"
+        );
+        assert_eq!(campaign.records()[1].stage, Stage::FindTargets);
+    }
+
+    #[test]
+    fn complete_campaign_does_nothing_when_run_again() {
+        let supervillain =
+            supervillain_with_targets(test_common::TARGETS.map(Location::from).to_vec());
+        let mut campaign = Campaign::new();
+        assert_ok!(campaign.run(&supervillain, &mut obedient_henchman(), &MockGadget::new()));
+
+        assert_ok!(campaign.run(&supervillain, &mut MockHenchman::new(), &MockGadget::new()));
+
+        assert_eq!(campaign.records().len(), Stage::ALL.len());
+    }
+
+    #[test]
+    fn campaign_can_be_simulated_with_basic_henchman() {
+        let supervillain =
+            supervillain_with_targets(test_common::TARGETS.map(Location::from).to_vec());
+        let mut henchman = BasicHenchman::new("Bob", HenchmanKind::Basic);
+        let mut campaign = Campaign::new();
+
+        assert_ok!(campaign.run(&supervillain, &mut henchman, &MockGadget::new()));
+
+        assert_eq!(henchman.reports().len(), 3);
+        assert_eq!(henchman.stamina(), 25);
+    }
+
+    #[test]
+    fn campaign_stops_at_henchman_failure_and_resumes_with_another_one() {
+        let supervillain =
+            supervillain_with_targets(test_common::TARGETS.map(Location::from).to_vec());
+        let mut engineer = BasicHenchman::new("Bob", HenchmanKind::Engineer);
+        let mut campaign = Campaign::new();
+
+        let result = campaign.run(&supervillain, &mut engineer, &MockGadget::new());
+
+        assert_matches!(result, Err(EvilError::StageFailed { stage, .. }) if stage == "fight enemies");
+        assert_eq!(campaign.next_stage(), Some(Stage::FightEnemies));
+        let mut basic = BasicHenchman::new("Kevin", HenchmanKind::Basic);
+        assert_ok!(campaign.run(&supervillain, &mut basic, &MockGadget::new()));
+        assert_eq!(basic.reports().len(), 2);
+    }
+
+    #[test]
+    fn campaign_retries_stage2_tasks() {
+        let supervillain =
+            supervillain_with_targets(test_common::TARGETS.map(Location::from).to_vec());
+        let mut henchman = MockHenchman::new();
+        let mut sequence = Sequence::new();
+        henchman
+            .expect_build_secret_hq()
+            .returning(|location| Ok(test_common::outcome(Task::BuildSecretHq { location })));
+        henchman
+            .expect_fight_enemies()
+            .once()
+            .in_sequence(&mut sequence)
+            .returning(|| {
+                Err(HenchmanError::Exhausted {
+                    name: String::from("Bob"),
+                    stamina: 0,
+                    required: 10,
+                })
+            });
+        henchman
+            .expect_fight_enemies()
+            .once()
+            .in_sequence(&mut sequence)
+            .returning(|| Ok(test_common::outcome(Task::FightEnemies)));
+        henchman
+            .expect_do_hard_things()
+            .returning(|| Ok(test_common::outcome(Task::DoHardThings)));
+        let mut campaign = Campaign::new().with_retries(1);
+
+        assert_ok!(campaign.run(&supervillain, &mut henchman, &MockGadget::new()));
+
+        assert!(campaign.is_complete());
+    }
+}
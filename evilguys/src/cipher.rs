@@ -1,11 +1,562 @@
-//! Module for sideckicks and all the related functionality
+//! Module for ciphers and all the related functionality
 #![allow(dead_code)]
 
+use std::fmt::{self, Display, Write};
+
+use argon2::Argon2;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chacha20poly1305::{
+    ChaCha20Poly1305, KeyInit, Nonce,
+    aead::{Aead, Payload},
+};
 #[cfg(test)]
 use mockall::automock;
+use rand::Rng;
+
+use crate::supervillain::EvilError;
+
+const ALPHABET_LEN: u8 = 26;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const KEY_LEN: usize = 32;
 
-/// Type that represents a sidekick.
+/// Trait that represents a cipher.
 #[cfg_attr(test, automock)]
 pub trait Cipher {
-    fn transform(&self, secret: &str, key: &str) -> String;
+    /// Ciphers the secret with the given key.
+    ///
+    /// # Errors
+    /// - `EvilError::CipherError` with `CipherErrorKind::InvalidKey` if the key can't be used by
+    ///   this cipher.
+    fn transform(&self, secret: &str, key: &str) -> Result<String, EvilError>;
+}
+
+/// Trait that represents the reverse operation of a cipher.
+///
+/// `restore` must recover the original secret from the output of the matching
+/// [`Cipher::transform`] when the same key is used.
+#[cfg_attr(test, automock)]
+pub trait Decipher {
+    /// Recovers the secret from a ciphered message.
+    ///
+    /// # Errors
+    /// - `EvilError::CipherError` with `CipherErrorKind::InvalidKey` if the key can't be used by
+    ///   this cipher.
+    /// - `EvilError::CipherError` with `CipherErrorKind::BadCiphertext` if the message wasn't
+    ///   produced by this cipher.
+    /// - `EvilError::CipherError` with `CipherErrorKind::AuthenticationFailed` if the cipher
+    ///   detects that the message or the key don't match.
+    fn restore(&self, ciphered: &str, key: &str) -> Result<String, EvilError>;
+}
+
+/// Caesar cipher that shifts ASCII letters a fixed number of positions.
+///
+/// The key is ignored, since the shift is part of the cipher itself. Any character that isn't an
+/// ASCII letter is left untouched.
+///
+/// # Examples
+/// ```
+///# use evil::{Cipher, Decipher, cipher::Caesar};
+/// let caesar = Caesar::new(3);
+/// assert_eq!(caesar.transform("Hello, world!", "").unwrap(), "Khoor, zruog!");
+/// assert_eq!(caesar.restore("Khoor, zruog!", "").unwrap(), "Hello, world!");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Caesar {
+    shift: u8,
+}
+
+impl Caesar {
+    #[must_use]
+    pub const fn new(shift: u8) -> Self {
+        Caesar {
+            shift: shift % ALPHABET_LEN,
+        }
+    }
+}
+
+impl Default for Caesar {
+    fn default() -> Self {
+        Caesar::new(3)
+    }
+}
+
+impl Cipher for Caesar {
+    fn transform(&self, secret: &str, _key: &str) -> Result<String, EvilError> {
+        Ok(secret
+            .chars()
+            .map(|c| shift_letter(c, self.shift))
+            .collect())
+    }
+}
+
+impl Decipher for Caesar {
+    fn restore(&self, ciphered: &str, _key: &str) -> Result<String, EvilError> {
+        let shift = (ALPHABET_LEN - self.shift) % ALPHABET_LEN;
+        Ok(ciphered.chars().map(|c| shift_letter(c, shift)).collect())
+    }
+}
+
+/// Vigenère cipher that uses the letters of the key as successive shifts.
+///
+/// Only the ASCII letters of the key are taken into account and the key only advances when a
+/// letter of the secret is ciphered. A key without letters is rejected.
+///
+/// # Examples
+/// ```
+///# use evil::{Cipher, Decipher, cipher::Vigenere};
+/// assert_eq!(Vigenere.transform("Attack at dawn", "lemon").unwrap(), "Lxfopv ef rnhr");
+/// assert_eq!(Vigenere.restore("Lxfopv ef rnhr", "lemon").unwrap(), "Attack at dawn");
+/// assert!(Vigenere.transform("Attack at dawn", "1234").is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Vigenere;
+
+impl Vigenere {
+    fn apply(text: &str, shifts: &[u8]) -> String {
+        let mut shifts = shifts.iter().cycle();
+        text.chars()
+            .map(|c| {
+                if c.is_ascii_alphabetic() {
+                    shift_letter(c, *shifts.next().expect("Cycle over non-empty shifts"))
+                } else {
+                    c
+                }
+            })
+            .collect()
+    }
+
+    fn shifts(key: &str) -> Result<Vec<u8>, EvilError> {
+        let shifts = key
+            .bytes()
+            .filter(u8::is_ascii_alphabetic)
+            .map(|b| b.to_ascii_lowercase() - b'a')
+            .collect::<Vec<_>>();
+        if shifts.is_empty() {
+            Err(cipher_error(
+                CipherErrorKind::InvalidKey,
+                "Key must contain ASCII letters",
+            ))
+        } else {
+            Ok(shifts)
+        }
+    }
+}
+
+impl Cipher for Vigenere {
+    fn transform(&self, secret: &str, key: &str) -> Result<String, EvilError> {
+        Ok(Vigenere::apply(secret, &Vigenere::shifts(key)?))
+    }
+}
+
+impl Decipher for Vigenere {
+    fn restore(&self, ciphered: &str, key: &str) -> Result<String, EvilError> {
+        let inverse_shifts = Vigenere::shifts(key)?
+            .iter()
+            .map(|shift| (ALPHABET_LEN - shift) % ALPHABET_LEN)
+            .collect::<Vec<_>>();
+        Ok(Vigenere::apply(ciphered, &inverse_shifts))
+    }
+}
+
+/// Text encoding used to represent the bytes produced by [`Xor`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum XorEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+/// Cipher that XORs the bytes of the secret with the bytes of the key, repeating the key as many
+/// times as needed.
+///
+/// The result is usually not valid UTF-8, so it is returned encoded as hexadecimal or base64.
+///
+/// # Examples
+/// ```
+///# use evil::{Cipher, Decipher, cipher::{Xor, XorEncoding}};
+/// let xor = Xor::new(XorEncoding::Hex);
+/// assert_eq!(xor.transform("abc", "A").unwrap(), "202322");
+/// assert_eq!(xor.restore("202322", "A").unwrap(), "abc");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Xor {
+    encoding: XorEncoding,
+}
+
+impl Xor {
+    #[must_use]
+    pub const fn new(encoding: XorEncoding) -> Self {
+        Xor { encoding }
+    }
+}
+
+impl Cipher for Xor {
+    fn transform(&self, secret: &str, key: &str) -> Result<String, EvilError> {
+        let ciphered = xor_with_key(secret.as_bytes(), key.as_bytes())?;
+        Ok(self.encoding.encode(&ciphered))
+    }
+}
+
+impl Decipher for Xor {
+    fn restore(&self, ciphered: &str, key: &str) -> Result<String, EvilError> {
+        let bytes = self.encoding.decode(ciphered).ok_or_else(|| {
+            cipher_error(
+                CipherErrorKind::BadCiphertext,
+                "Message is not properly encoded",
+            )
+        })?;
+        String::from_utf8(xor_with_key(&bytes, key.as_bytes())?)
+            .map_err(|err| cipher_error(CipherErrorKind::BadCiphertext, &err.to_string()))
+    }
+}
+
+impl XorEncoding {
+    fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            XorEncoding::Hex => bytes.iter().fold(String::new(), |mut hex, byte| {
+                let _ = write!(hex, "{byte:02x}");
+                hex
+            }),
+            XorEncoding::Base64 => STANDARD.encode(bytes),
+        }
+    }
+
+    fn decode(self, text: &str) -> Option<Vec<u8>> {
+        match self {
+            XorEncoding::Hex => {
+                if !text.len().is_multiple_of(2) {
+                    return None;
+                }
+                (0..text.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+                    .collect()
+            }
+            XorEncoding::Base64 => STANDARD.decode(text).ok(),
+        }
+    }
+}
+
+/// Authenticated cipher based on ChaCha20-Poly1305.
+///
+/// A fresh random salt is used to derive the encryption key from the shared key with Argon2id,
+/// so keys derived from weak passphrases are expensive to brute-force, and a fresh random nonce is used for every message. Both are prepended to the
+/// ciphertext and the result is encoded as base64, so ciphering the same secret twice produces
+/// different outputs. Any modification of the ciphered message is detected when restoring it.
+///
+/// # Examples
+/// ```
+///# use evil::{Cipher, Decipher, cipher::Authenticated};
+/// let ciphered = Authenticated.transform("Attack at dawn", "Kryptonite").unwrap();
+/// assert_eq!(Authenticated.restore(&ciphered, "Kryptonite").unwrap(), "Attack at dawn");
+/// assert!(Authenticated.restore(&ciphered, "Wrong key").is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Authenticated;
+
+impl Authenticated {
+    fn aead(key: &str, salt: &[u8]) -> Result<ChaCha20Poly1305, EvilError> {
+        if key.is_empty() {
+            return Err(cipher_error(
+                CipherErrorKind::InvalidKey,
+                "Key must not be empty",
+            ));
+        }
+        let mut derived_key = [0u8; KEY_LEN];
+        Argon2::default()
+            .hash_password_into(key.as_bytes(), salt, &mut derived_key)
+            .map_err(|err| cipher_error(CipherErrorKind::InvalidKey, &err.to_string()))?;
+        Ok(ChaCha20Poly1305::new(&derived_key.into()))
+    }
+}
+
+impl Cipher for Authenticated {
+    fn transform(&self, secret: &str, key: &str) -> Result<String, EvilError> {
+        let mut rng = rand::rng();
+        let salt: [u8; SALT_LEN] = rng.random();
+        let nonce: [u8; NONCE_LEN] = rng.random();
+        let ciphered = Authenticated::aead(key, &salt)?
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: secret.as_bytes(),
+                    aad: &salt,
+                },
+            )
+            .map_err(|_| {
+                cipher_error(CipherErrorKind::CipheringFailed, "Unable to cipher secret")
+            })?;
+        Ok(STANDARD.encode([&salt[..], &nonce, &ciphered].concat()))
+    }
+}
+
+impl Decipher for Authenticated {
+    fn restore(&self, ciphered: &str, key: &str) -> Result<String, EvilError> {
+        let bytes = STANDARD
+            .decode(ciphered)
+            .map_err(|err| cipher_error(CipherErrorKind::BadCiphertext, &err.to_string()))?;
+        if bytes.len() < SALT_LEN + NONCE_LEN + TAG_LEN {
+            return Err(cipher_error(
+                CipherErrorKind::BadCiphertext,
+                "Message too short",
+            ));
+        }
+        let (salt, rest) = bytes.split_at(SALT_LEN);
+        let (nonce, payload) = rest.split_at(NONCE_LEN);
+        let plain = Authenticated::aead(key, salt)?
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: payload,
+                    aad: salt,
+                },
+            )
+            .map_err(|_| {
+                cipher_error(
+                    CipherErrorKind::AuthenticationFailed,
+                    "Message or key don't match",
+                )
+            })?;
+        String::from_utf8(plain)
+            .map_err(|err| cipher_error(CipherErrorKind::BadCiphertext, &err.to_string()))
+    }
+}
+
+/// Reasons for a cipher operation to fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CipherErrorKind {
+    InvalidKey,
+    BadCiphertext,
+    AuthenticationFailed,
+    CipheringFailed,
+}
+
+impl Display for CipherErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            CipherErrorKind::InvalidKey => "invalid key",
+            CipherErrorKind::BadCiphertext => "bad ciphertext",
+            CipherErrorKind::AuthenticationFailed => "authentication failed",
+            CipherErrorKind::CipheringFailed => "ciphering failed",
+        };
+        f.write_str(kind)
+    }
+}
+
+fn cipher_error(kind: CipherErrorKind, reason: &str) -> EvilError {
+    EvilError::CipherError {
+        kind,
+        reason: reason.to_string(),
+    }
+}
+
+fn shift_letter(c: char, shift: u8) -> char {
+    let base = match c {
+        'a'..='z' => b'a',
+        'A'..='Z' => b'A',
+        _ => return c,
+    };
+    let offset = (c as u8 - base + shift) % ALPHABET_LEN;
+    char::from(base + offset)
+}
+
+fn xor_with_key(data: &[u8], key: &[u8]) -> Result<Vec<u8>, EvilError> {
+    if key.is_empty() {
+        return Err(cipher_error(
+            CipherErrorKind::InvalidKey,
+            "Key must not be empty",
+        ));
+    }
+    Ok(data
+        .iter()
+        .zip(key.iter().cycle())
+        .map(|(byte, key_byte)| byte ^ key_byte)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use assertables::{assert_matches, assert_ok_eq_x};
+
+    use crate::test_common;
+
+    use super::*;
+
+    const KEY: &str = "Supervillain";
+
+    fn round_trip<C: Cipher + Decipher>(sut: &C) -> Result<(), EvilError> {
+        let ciphered = sut.transform(test_common::MAIN_SECRET_MESSAGE, KEY)?;
+
+        assert_ne!(ciphered, test_common::MAIN_SECRET_MESSAGE);
+        assert_ok_eq_x!(
+            sut.restore(&ciphered, KEY).as_deref(),
+            test_common::MAIN_SECRET_MESSAGE
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn caesar_shifts_letters_and_keeps_other_chars() {
+        let sut = Caesar::new(1);
+
+        assert_ok_eq_x!(sut.transform("Zebra, 42!", KEY).as_deref(), "Afcsb, 42!");
+    }
+
+    #[test]
+    fn caesar_round_trips() -> Result<(), EvilError> {
+        round_trip(&Caesar::new(7))
+    }
+
+    #[test]
+    fn vigenere_uses_key_letters_as_shifts() {
+        assert_ok_eq_x!(
+            Vigenere.transform("ATTACKATDAWN", "LEMON").as_deref(),
+            "LXFOPVEFRNHR"
+        );
+    }
+
+    #[test]
+    fn vigenere_with_key_without_letters_is_invalid_key() {
+        let result = Vigenere.transform(test_common::MAIN_SECRET_MESSAGE, "1234");
+
+        assert_matches!(
+            result,
+            Err(EvilError::CipherError {
+                kind: CipherErrorKind::InvalidKey,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn vigenere_round_trips() -> Result<(), EvilError> {
+        round_trip(&Vigenere)
+    }
+
+    #[test]
+    fn xor_hex_round_trips() -> Result<(), EvilError> {
+        round_trip(&Xor::new(XorEncoding::Hex))
+    }
+
+    #[test]
+    fn xor_base64_round_trips() -> Result<(), EvilError> {
+        round_trip(&Xor::new(XorEncoding::Base64))
+    }
+
+    #[test]
+    fn xor_with_empty_key_is_invalid_key() {
+        let result = Xor::new(XorEncoding::Base64).transform("abc", "");
+
+        assert_matches!(
+            result,
+            Err(EvilError::CipherError {
+                kind: CipherErrorKind::InvalidKey,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn xor_restore_with_invalid_hex_is_bad_ciphertext() {
+        let result = Xor::new(XorEncoding::Hex).restore("abc", KEY);
+
+        assert_matches!(
+            result,
+            Err(EvilError::CipherError {
+                kind: CipherErrorKind::BadCiphertext,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn authenticated_round_trips() -> Result<(), EvilError> {
+        round_trip(&Authenticated)
+    }
+
+    #[test]
+    fn authenticated_uses_fresh_salt_and_nonce_for_every_message() -> Result<(), EvilError> {
+        let first = Authenticated.transform(test_common::MAIN_SECRET_MESSAGE, KEY)?;
+        let second = Authenticated.transform(test_common::MAIN_SECRET_MESSAGE, KEY)?;
+
+        assert_ne!(first, second);
+        Ok(())
+    }
+
+    #[test]
+    fn authenticated_with_empty_key_is_invalid_key() {
+        let result = Authenticated.transform(test_common::MAIN_SECRET_MESSAGE, "");
+
+        assert_matches!(
+            result,
+            Err(EvilError::CipherError {
+                kind: CipherErrorKind::InvalidKey,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn authenticated_with_wrong_key_fails_authentication() -> Result<(), EvilError> {
+        let ciphered = Authenticated.transform(test_common::MAIN_SECRET_MESSAGE, KEY)?;
+
+        let result = Authenticated.restore(&ciphered, "Not the key");
+
+        assert_matches!(
+            result,
+            Err(EvilError::CipherError {
+                kind: CipherErrorKind::AuthenticationFailed,
+                ..
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn authenticated_with_tampered_message_fails_authentication() -> Result<(), EvilError> {
+        let ciphered = Authenticated.transform(test_common::MAIN_SECRET_MESSAGE, KEY)?;
+        let mut bytes = STANDARD.decode(ciphered).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+
+        let result = Authenticated.restore(&STANDARD.encode(bytes), KEY);
+
+        assert_matches!(
+            result,
+            Err(EvilError::CipherError {
+                kind: CipherErrorKind::AuthenticationFailed,
+                ..
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn authenticated_with_truncated_message_is_bad_ciphertext() {
+        let result = Authenticated.restore("c2hvcnQ=", KEY);
+
+        assert_matches!(
+            result,
+            Err(EvilError::CipherError {
+                kind: CipherErrorKind::BadCiphertext,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn authenticated_without_tag_is_bad_ciphertext() {
+        let result = Authenticated.restore(&STANDARD.encode([0u8; SALT_LEN + NONCE_LEN + 4]), KEY);
+
+        assert_matches!(
+            result,
+            Err(EvilError::CipherError {
+                kind: CipherErrorKind::BadCiphertext,
+                ..
+            })
+        );
+    }
 }
@@ -0,0 +1,279 @@
+//! Module for crews of henchmen working together
+use std::cmp::Reverse;
+
+use thiserror::Error;
+
+use crate::henchman::{Henchman, HenchmanError, Outcome, Task};
+
+/// Policies to choose which members of a crew perform a task.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AssignmentPolicy {
+    /// Members take turns, one task each, in the order they joined.
+    #[default]
+    RoundRobin,
+    /// The member with the highest level of the required skill. Ties go to whoever joined first.
+    BySkill,
+    /// Every member performs every task.
+    AllHands,
+}
+
+/// Reasons why a crew fails to perform a task.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CrewError {
+    #[error("Crew without henchmen")]
+    Empty,
+    #[error(transparent)]
+    Henchman(#[from] HenchmanError),
+}
+
+/// Results of a task performed by a crew.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrewReport {
+    pub task: Task,
+    /// Result of every member assigned to the task, with the position of the member in the crew.
+    pub results: Vec<(usize, Result<Outcome, HenchmanError>)>,
+}
+
+impl CrewReport {
+    /// Returns whether any of the assigned members performed the task.
+    #[must_use]
+    pub fn succeeded(&self) -> bool {
+        self.results.iter().any(|(_, result)| result.is_ok())
+    }
+
+    /// Returns the report if any of the assigned members performed the task.
+    ///
+    /// # Errors
+    /// - `CrewError::Henchman` of the last assigned member if none performed the task.
+    /// - `CrewError::Empty` if no member was assigned.
+    pub fn into_result(self) -> Result<Self, CrewError> {
+        if self.succeeded() {
+            return Ok(self);
+        }
+        match self.results.into_iter().next_back() {
+            Some((_, Err(err))) => Err(CrewError::Henchman(err)),
+            _ => Err(CrewError::Empty),
+        }
+    }
+}
+
+/// Group of henchmen that share the tasks according to an assignment policy.
+///
+/// # Examples
+/// ```
+///# use evil::crew::{AssignmentPolicy, Crew};
+///# use evil::henchman::{BasicHenchman, HenchmanKind, Task};
+/// let mut crew = Crew::new(AssignmentPolicy::BySkill);
+/// crew.push(Box::new(BasicHenchman::new("Bob", HenchmanKind::Brute)));
+/// crew.push(Box::new(BasicHenchman::new("Kevin", HenchmanKind::Spy)));
+/// let report = crew.perform(Task::DoHardThings);
+/// assert!(report.succeeded());
+/// assert_eq!(report.results[0].0, 1);
+/// ```
+pub struct Crew<'a> {
+    members: Vec<Box<dyn Henchman + 'a>>,
+    policy: AssignmentPolicy,
+    next: usize,
+}
+
+impl<'a> Crew<'a> {
+    #[must_use]
+    pub fn new(policy: AssignmentPolicy) -> Self {
+        Crew {
+            members: vec![],
+            policy,
+            next: 0,
+        }
+    }
+
+    /// Adds a member at the end of the crew.
+    pub fn push(&mut self, henchman: Box<dyn Henchman + 'a>) {
+        self.members.push(henchman);
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    #[must_use]
+    pub fn policy(&self) -> AssignmentPolicy {
+        self.policy
+    }
+
+    /// Assigns the task to members according to the policy and reports their results. An
+    /// empty crew reports no results.
+    pub fn perform(&mut self, task: Task) -> CrewReport {
+        let results = self
+            .assignees(&task)
+            .into_iter()
+            .map(|index| (index, dispatch(&mut *self.members[index], task.clone())))
+            .collect();
+        CrewReport { task, results }
+    }
+
+    fn assignees(&mut self, task: &Task) -> Vec<usize> {
+        if self.members.is_empty() {
+            return vec![];
+        }
+        match self.policy {
+            AssignmentPolicy::RoundRobin => {
+                let index = self.next % self.members.len();
+                self.next = index + 1;
+                vec![index]
+            }
+            AssignmentPolicy::BySkill => {
+                let skill = task.skill();
+                (0..self.members.len())
+                    .min_by_key(|&index| Reverse(self.members[index].skill_level(skill)))
+                    .into_iter()
+                    .collect()
+            }
+            AssignmentPolicy::AllHands => (0..self.members.len()).collect(),
+        }
+    }
+}
+
+fn dispatch(henchman: &mut dyn Henchman, task: Task) -> Result<Outcome, HenchmanError> {
+    match task {
+        Task::BuildSecretHq { location } => henchman.build_secret_hq(location),
+        Task::DoHardThings => henchman.do_hard_things(),
+        Task::FightEnemies => henchman.fight_enemies(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertables::assert_matches;
+
+    use super::*;
+    use crate::henchman::{BasicHenchman, HenchmanKind, MockHenchman, Skill};
+    use crate::test_common;
+
+    fn indexes(report: &CrewReport) -> Vec<usize> {
+        report.results.iter().map(|(index, _)| *index).collect()
+    }
+
+    fn mixed_crew(policy: AssignmentPolicy) -> Crew<'static> {
+        let mut crew = Crew::new(policy);
+        crew.push(Box::new(BasicHenchman::new("Bob", HenchmanKind::Brute)));
+        crew.push(Box::new(BasicHenchman::new(
+            "Stuart",
+            HenchmanKind::Engineer,
+        )));
+        crew.push(Box::new(BasicHenchman::new("Kevin", HenchmanKind::Spy)));
+        crew
+    }
+
+    #[test]
+    fn round_robin_members_take_turns() {
+        let mut crew = mixed_crew(AssignmentPolicy::RoundRobin);
+
+        let assigned: Vec<_> = (0..4)
+            .map(|_| indexes(&crew.perform(Task::FightEnemies)))
+            .collect();
+
+        assert_eq!(assigned, [[0], [1], [2], [0]]);
+    }
+
+    #[test]
+    fn by_skill_assigns_the_most_skilled_member() {
+        let mut crew = mixed_crew(AssignmentPolicy::BySkill);
+
+        let report = crew.perform(Task::BuildSecretHq {
+            location: String::from(test_common::FIRST_TARGET),
+        });
+
+        assert_eq!(indexes(&report), [1]);
+        assert!(report.succeeded());
+    }
+
+    #[test]
+    fn by_skill_ties_go_to_the_first_member() {
+        let mut crew = Crew::new(AssignmentPolicy::BySkill);
+        for _ in 0..2 {
+            let mut henchman = MockHenchman::new();
+            henchman.expect_skill_level().return_const(7);
+            henchman
+                .expect_fight_enemies()
+                .returning(|| Ok(test_common::outcome(Task::FightEnemies)));
+            crew.push(Box::new(henchman));
+        }
+
+        assert_eq!(indexes(&crew.perform(Task::FightEnemies)), [0]);
+    }
+
+    #[test]
+    fn all_hands_assigns_every_member() {
+        let mut crew = mixed_crew(AssignmentPolicy::AllHands);
+
+        let report = crew.perform(Task::FightEnemies);
+
+        assert_eq!(indexes(&report), [0, 1, 2]);
+        assert!(report.results[0].1.is_ok());
+        assert!(report.results[1].1.is_err());
+        assert!(report.into_result().is_ok());
+    }
+
+    #[test]
+    fn report_without_successes_is_the_last_error() {
+        let mut crew = Crew::new(AssignmentPolicy::AllHands);
+        crew.push(Box::new(BasicHenchman::new("Bob", HenchmanKind::Brute)));
+        crew.push(Box::new(BasicHenchman::new("Kevin", HenchmanKind::Spy)));
+
+        let result = crew
+            .perform(Task::BuildSecretHq {
+                location: String::from(test_common::FIRST_TARGET),
+            })
+            .into_result();
+
+        assert_matches!(
+            result,
+            Err(CrewError::Henchman(HenchmanError::Unskilled {
+                skill: Skill::Engineering,
+                level: 3,
+                ..
+            }))
+        );
+    }
+
+    #[test]
+    fn empty_crew_reports_nothing() {
+        let mut crew = Crew::new(AssignmentPolicy::RoundRobin);
+
+        let report = crew.perform(Task::DoHardThings);
+
+        assert!(crew.is_empty());
+        assert!(report.results.is_empty());
+        assert_eq!(report.into_result(), Err(CrewError::Empty));
+    }
+
+    #[test]
+    fn members_without_skills_tie_at_the_lowest_level() {
+        struct Unskilled;
+        impl Henchman for Unskilled {
+            fn build_secret_hq(&mut self, location: String) -> Result<Outcome, HenchmanError> {
+                Ok(test_common::outcome(Task::BuildSecretHq { location }))
+            }
+            fn do_hard_things(&self) -> Result<Outcome, HenchmanError> {
+                Ok(test_common::outcome(Task::DoHardThings))
+            }
+            fn fight_enemies(&self) -> Result<Outcome, HenchmanError> {
+                Ok(test_common::outcome(Task::FightEnemies))
+            }
+        }
+        let mut crew = Crew::new(AssignmentPolicy::BySkill);
+        crew.push(Box::new(Unskilled));
+        crew.push(Box::new(BasicHenchman::new("Bob", HenchmanKind::Brute)));
+
+        assert_eq!(Unskilled.skill_level(Skill::Combat), 0);
+        assert_eq!(indexes(&crew.perform(Task::FightEnemies)), [1]);
+    }
+}
@@ -1,6 +1,7 @@
 //! Module for gadgets and all the related functionality
 #![allow(dead_code)]
 
+use async_trait::async_trait;
 #[cfg(test)]
 use mockall::automock;
 
@@ -9,3 +10,12 @@ use mockall::automock;
 pub trait Gadget: Send {
     fn do_stuff(&self);
 }
+
+/// Asynchronous version of [`Gadget`] for long-running stuff.
+///
+/// The stuff returns a boxed future, so it can be used as `dyn AsyncGadget`.
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait AsyncGadget: Send + Sync {
+    async fn do_stuff(&self);
+}
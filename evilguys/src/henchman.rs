@@ -1,13 +1,464 @@
 //! Module to define henchmen.
 #![allow(dead_code)]
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    sync::Mutex,
+    time::Duration,
+};
 
+use async_trait::async_trait;
 #[cfg(test)]
-use mockall::automock;
+use mockall::{automock, mock};
+use thiserror::Error;
+
+/// Identifier of a henchman.
+pub type HenchmanId = u64;
 
 /// Henchman trait.
-#[cfg_attr(test, automock)]
+///
+/// Every task returns its outcome or the reason why the henchman couldn't perform it.
 pub trait Henchman {
-    fn build_secret_hq(&mut self, location: String);
-    fn do_hard_things(&self);
-    fn fight_enemies(&self);
+    /// # Errors
+    /// - `HenchmanError` if the headquarters cannot be built.
+    fn build_secret_hq(&mut self, location: String) -> Result<Outcome, HenchmanError>;
+    /// # Errors
+    /// - `HenchmanError` if the hard things cannot be done.
+    fn do_hard_things(&self) -> Result<Outcome, HenchmanError>;
+    /// # Errors
+    /// - `HenchmanError` if the enemies cannot be fought.
+    fn fight_enemies(&self) -> Result<Outcome, HenchmanError>;
+    /// Returns the level of the skill, from 0 to 10. Henchmen don't have any skill unless they
+    /// say otherwise.
+    fn skill_level(&self, _: Skill) -> u8 {
+        0
+    }
+}
+
+#[cfg(test)]
+mock! {
+    pub Henchman {}
+    impl Henchman for Henchman {
+        fn build_secret_hq(&mut self, location: String) -> Result<Outcome, HenchmanError>;
+        fn do_hard_things(&self) -> Result<Outcome, HenchmanError>;
+        fn fight_enemies(&self) -> Result<Outcome, HenchmanError>;
+        fn skill_level(&self, skill: Skill) -> u8;
+    }
+}
+
+/// Asynchronous version of [`Henchman`] for long-running tasks.
+///
+/// The tasks return boxed futures, so it can be used as `dyn AsyncHenchman`, and they take
+/// `&self`, so several tasks can run concurrently.
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait AsyncHenchman: Send + Sync {
+    /// # Errors
+    /// - `HenchmanError` if the headquarters cannot be built.
+    async fn build_secret_hq(&self, location: String) -> Result<Outcome, HenchmanError>;
+    /// # Errors
+    /// - `HenchmanError` if the hard things cannot be done.
+    async fn do_hard_things(&self) -> Result<Outcome, HenchmanError>;
+    /// # Errors
+    /// - `HenchmanError` if the enemies cannot be fought.
+    async fn fight_enemies(&self) -> Result<Outcome, HenchmanError>;
+}
+
+/// Skills that henchmen can have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Skill {
+    Engineering,
+    Combat,
+    Stealth,
+}
+
+impl Display for Skill {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let skill = match self {
+            Skill::Engineering => "engineering",
+            Skill::Combat => "combat",
+            Skill::Stealth => "stealth",
+        };
+        f.write_str(skill)
+    }
+}
+
+/// Task that a henchman can be asked to perform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Task {
+    BuildSecretHq { location: String },
+    DoHardThings,
+    FightEnemies,
+}
+
+impl Task {
+    /// Level of skill required to perform the task.
+    pub const REQUIRED_LEVEL: u8 = 5;
+
+    /// Returns the skill required to perform the task.
+    #[must_use]
+    pub fn skill(&self) -> Skill {
+        match self {
+            Task::BuildSecretHq { .. } => Skill::Engineering,
+            Task::DoHardThings => Skill::Stealth,
+            Task::FightEnemies => Skill::Combat,
+        }
+    }
+
+    /// Returns the stamina spent performing the task.
+    #[must_use]
+    pub fn stamina_cost(&self) -> u32 {
+        match self {
+            Task::BuildSecretHq { .. } => 30,
+            Task::DoHardThings => 20,
+            Task::FightEnemies => 25,
+        }
+    }
+}
+
+impl Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Task::BuildSecretHq { location } => write!(f, "build secret HQ in {location}"),
+            Task::DoHardThings => f.write_str("do hard things"),
+            Task::FightEnemies => f.write_str("fight enemies"),
+        }
+    }
+}
+
+/// Result of a task performed successfully.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    pub task: Task,
+    pub stamina_left: u32,
+}
+
+/// Reasons why a henchman fails to perform a task.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HenchmanError {
+    #[error("Henchman exhausted: name='{}', stamina={}, required={}", .name, .stamina, .required)]
+    Exhausted {
+        name: String,
+        stamina: u32,
+        required: u32,
+    },
+    #[error("Henchman unskilled: name='{}', skill='{}', level={}, required={}", .name, .skill, .level, .required)]
+    Unskilled {
+        name: String,
+        skill: Skill,
+        level: u8,
+        required: u8,
+    },
+    #[error("Henchman timed out: task='{}', timeout={}ms", .task, .timeout.as_millis())]
+    TimedOut { task: Task, timeout: Duration },
+    #[error("Henchman cancelled")]
+    Cancelled,
+}
+
+/// Kinds of henchmen, each one with its own skills and stamina.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HenchmanKind {
+    /// Good enough at everything.
+    #[default]
+    Basic,
+    /// Builds headquarters, but avoids fights.
+    Engineer,
+    /// Fights with plenty of stamina, but cannot build anything.
+    Brute,
+    /// Does the hard things unnoticed.
+    Spy,
+}
+
+impl HenchmanKind {
+    /// Returns the skill levels of this kind of henchman, from 0 to 10.
+    #[must_use]
+    pub fn skills(self) -> BTreeMap<Skill, u8> {
+        let (engineering, combat, stealth) = match self {
+            HenchmanKind::Basic => (5, 5, 5),
+            HenchmanKind::Engineer => (8, 2, 3),
+            HenchmanKind::Brute => (2, 9, 1),
+            HenchmanKind::Spy => (3, 4, 9),
+        };
+        BTreeMap::from([
+            (Skill::Engineering, engineering),
+            (Skill::Combat, combat),
+            (Skill::Stealth, stealth),
+        ])
+    }
+
+    /// Returns the stamina of this kind of henchman when rested.
+    #[must_use]
+    pub fn max_stamina(self) -> u32 {
+        match self {
+            HenchmanKind::Basic => 100,
+            HenchmanKind::Engineer => 80,
+            HenchmanKind::Brute => 120,
+            HenchmanKind::Spy => 70,
+        }
+    }
+}
+
+/// Henchman with a name, skills and stamina.
+///
+/// A task succeeds if the henchman has enough stamina and the required skill level
+/// ([`Task::REQUIRED_LEVEL`]) for it. Only successful tasks spend stamina. Every task performed
+/// is reported, including the failed ones.
+///
+/// # Examples
+/// ```
+///# use evil::henchman::{BasicHenchman, HenchmanError, HenchmanKind, Task};
+/// let bob = BasicHenchman::new("Bob", HenchmanKind::Engineer);
+/// assert!(bob.perform(Task::BuildSecretHq { location: "Tampa".to_string() }).is_ok());
+/// assert!(matches!(
+///     bob.perform(Task::FightEnemies),
+///     Err(HenchmanError::Unskilled { .. })
+/// ));
+/// assert_eq!(bob.reports().len(), 2);
+/// ```
+#[derive(Debug)]
+pub struct BasicHenchman {
+    pub name: String,
+    pub kind: HenchmanKind,
+    pub skills: BTreeMap<Skill, u8>,
+    pub max_stamina: u32,
+    state: Mutex<HenchmanState>,
+}
+
+#[derive(Debug)]
+struct HenchmanState {
+    stamina: u32,
+    reports: Vec<Result<Outcome, HenchmanError>>,
+}
+
+impl BasicHenchman {
+    /// Creates a rested henchman with the skills of its kind.
+    #[must_use]
+    pub fn new(name: &str, kind: HenchmanKind) -> Self {
+        BasicHenchman {
+            name: name.to_string(),
+            kind,
+            skills: kind.skills(),
+            max_stamina: kind.max_stamina(),
+            state: Mutex::new(HenchmanState {
+                stamina: kind.max_stamina(),
+                reports: vec![],
+            }),
+        }
+    }
+
+    #[must_use]
+    pub fn stamina(&self) -> u32 {
+        self.state().stamina
+    }
+
+    /// Returns the results of the tasks performed, in order.
+    #[must_use]
+    pub fn reports(&self) -> Vec<Result<Outcome, HenchmanError>> {
+        self.state().reports.clone()
+    }
+
+    /// Recovers all the stamina.
+    pub fn rest(&self) {
+        self.state().stamina = self.max_stamina;
+    }
+
+    /// Performs the task and reports the result.
+    ///
+    /// # Errors
+    /// - `HenchmanError::Exhausted` if there isn't enough stamina left for the task.
+    /// - `HenchmanError::Unskilled` if the level of the required skill is too low.
+    pub fn perform(&self, task: Task) -> Result<Outcome, HenchmanError> {
+        let mut state = self.state();
+        let skill = task.skill();
+        let level = self.skills.get(&skill).copied().unwrap_or_default();
+        let required = task.stamina_cost();
+        let result = if state.stamina < required {
+            Err(HenchmanError::Exhausted {
+                name: self.name.clone(),
+                stamina: state.stamina,
+                required,
+            })
+        } else if level < Task::REQUIRED_LEVEL {
+            Err(HenchmanError::Unskilled {
+                name: self.name.clone(),
+                skill,
+                level,
+                required: Task::REQUIRED_LEVEL,
+            })
+        } else {
+            state.stamina -= required;
+            Ok(Outcome {
+                task,
+                stamina_left: state.stamina,
+            })
+        };
+        state.reports.push(result.clone());
+        result
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, HenchmanState> {
+        self.state
+            .lock()
+            .expect("Henchman state shouldn't be poisoned")
+    }
+}
+
+impl Henchman for BasicHenchman {
+    fn build_secret_hq(&mut self, location: String) -> Result<Outcome, HenchmanError> {
+        self.perform(Task::BuildSecretHq { location })
+    }
+
+    fn do_hard_things(&self) -> Result<Outcome, HenchmanError> {
+        self.perform(Task::DoHardThings)
+    }
+
+    fn fight_enemies(&self) -> Result<Outcome, HenchmanError> {
+        self.perform(Task::FightEnemies)
+    }
+
+    fn skill_level(&self, skill: Skill) -> u8 {
+        self.skills.get(&skill).copied().unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl AsyncHenchman for BasicHenchman {
+    async fn build_secret_hq(&self, location: String) -> Result<Outcome, HenchmanError> {
+        self.perform(Task::BuildSecretHq { location })
+    }
+
+    async fn do_hard_things(&self) -> Result<Outcome, HenchmanError> {
+        self.perform(Task::DoHardThings)
+    }
+
+    async fn fight_enemies(&self) -> Result<Outcome, HenchmanError> {
+        self.perform(Task::FightEnemies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertables::{assert_err, assert_matches, assert_ok};
+
+    use super::*;
+    use crate::test_common;
+
+    #[test]
+    fn new_henchman_is_rested_with_skills_of_its_kind() {
+        let henchman = BasicHenchman::new("Bob", HenchmanKind::Brute);
+
+        assert_eq!(henchman.stamina(), 120);
+        assert_eq!(henchman.skills.get(&Skill::Combat), Some(&9));
+        assert!(henchman.reports().is_empty());
+    }
+
+    #[test]
+    fn successful_task_spends_stamina() {
+        let henchman = BasicHenchman::new("Bob", HenchmanKind::Basic);
+
+        let outcome = henchman.perform(Task::FightEnemies);
+
+        assert_eq!(
+            outcome,
+            Ok(Outcome {
+                task: Task::FightEnemies,
+                stamina_left: 75
+            })
+        );
+        assert_eq!(henchman.stamina(), 75);
+    }
+
+    #[test]
+    fn unskilled_henchman_fails_without_spending_stamina() {
+        let henchman = BasicHenchman::new("Bob", HenchmanKind::Brute);
+
+        let outcome = henchman.perform(Task::BuildSecretHq {
+            location: String::from(test_common::FIRST_TARGET),
+        });
+
+        assert_matches!(
+            outcome,
+            Err(HenchmanError::Unskilled {
+                skill: Skill::Engineering,
+                level: 2,
+                ..
+            })
+        );
+        assert_eq!(henchman.stamina(), 120);
+    }
+
+    #[test]
+    fn exhausted_henchman_fails_until_rested() {
+        let henchman = BasicHenchman::new("Bob", HenchmanKind::Spy);
+        for _ in 0..3 {
+            assert_ok!(henchman.perform(Task::DoHardThings));
+        }
+
+        assert_matches!(
+            henchman.perform(Task::DoHardThings),
+            Err(HenchmanError::Exhausted {
+                stamina: 10,
+                required: 20,
+                ..
+            })
+        );
+        henchman.rest();
+        assert_ok!(henchman.perform(Task::DoHardThings));
+    }
+
+    #[test]
+    fn trait_methods_perform_and_report_tasks() {
+        let mut henchman = BasicHenchman::new("Bob", HenchmanKind::Engineer);
+
+        assert_ok!(Henchman::build_secret_hq(
+            &mut henchman,
+            String::from(test_common::FIRST_TARGET)
+        ));
+        assert_err!(Henchman::fight_enemies(&henchman));
+        assert_err!(Henchman::do_hard_things(&henchman));
+
+        let reports = henchman.reports();
+        assert_eq!(reports.len(), 3);
+        assert!(reports[0].is_ok());
+        assert_matches!(
+            &reports[1],
+            Err(HenchmanError::Unskilled {
+                skill: Skill::Combat,
+                ..
+            })
+        );
+        assert_matches!(
+            &reports[2],
+            Err(HenchmanError::Unskilled {
+                skill: Skill::Stealth,
+                ..
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn async_tasks_perform_and_report_tasks() {
+        let henchman: &dyn AsyncHenchman = &BasicHenchman::new("Kevin", HenchmanKind::Spy);
+
+        assert_ok!(henchman.do_hard_things().await);
+        assert_err!(
+            henchman
+                .build_secret_hq(String::from(test_common::FIRST_TARGET))
+                .await
+        );
+        assert_eq!(
+            henchman.fight_enemies().await,
+            Err(HenchmanError::Unskilled {
+                name: String::from("Kevin"),
+                skill: Skill::Combat,
+                level: 4,
+                required: Task::REQUIRED_LEVEL,
+            })
+        );
+    }
 }
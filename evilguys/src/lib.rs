@@ -1,12 +1,21 @@
+pub mod campaign;
 pub mod cipher;
+pub mod crew;
 pub mod gadget;
 pub mod henchman;
+pub mod location;
+pub mod name;
+pub mod orders;
+pub mod plan;
+pub mod repository;
 pub mod sidekick;
 pub mod supervillain;
+pub mod target;
 #[cfg(test)]
 mod test_common;
 
-pub use cipher::Cipher;
+pub use cipher::{Cipher, Decipher};
+pub use crew::Crew;
 pub use gadget::Gadget;
 pub use henchman::Henchman;
 pub use sidekick::Sidekick;
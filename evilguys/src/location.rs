@@ -0,0 +1,355 @@
+//! Module for locations and the listings that describe them
+use std::{
+    fmt::{self, Display},
+    io::{self, BufRead},
+    str::FromStr,
+};
+
+use crate::supervillain::EvilError;
+
+/// How well defended a location is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Strength {
+    Weak,
+    Strong,
+}
+
+impl FromStr for Strength {
+    type Err = String;
+
+    fn from_str(strength: &str) -> Result<Self, Self::Err> {
+        match strength.to_lowercase().as_str() {
+            "weak" => Ok(Strength::Weak),
+            "strong" => Ok(Strength::Strong),
+            _ => Err(format!("Unknown strength '{strength}'")),
+        }
+    }
+}
+
+impl Display for Strength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let strength = match self {
+            Strength::Weak => "weak",
+            Strength::Strong => "strong",
+        };
+        f.write_str(strength)
+    }
+}
+
+/// Geographic coordinates in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Coordinates {
+    /// Mean radius of the Earth, in kilometers.
+    const EARTH_RADIUS_KM: f64 = 6371.0;
+
+    /// Returns the great-circle distance to other coordinates, in kilometers.
+    ///
+    /// # Examples
+    /// ```
+    ///# use evil::location::Coordinates;
+    /// let madrid = Coordinates { latitude: 40.4168, longitude: -3.7038 };
+    /// let paris = Coordinates { latitude: 48.8566, longitude: 2.3522 };
+    /// let distance = madrid.distance_km(&paris);
+    /// assert!((1050.0..1056.0).contains(&distance));
+    /// ```
+    #[must_use]
+    pub fn distance_km(&self, other: &Coordinates) -> f64 {
+        let (lat1, lat2) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let delta_lat = lat2 - lat1;
+        let delta_lon = (other.longitude - self.longitude).to_radians();
+        let a = (delta_lat / 2.0).sin().powi(2)
+            + lat1.cos() * lat2.cos() * (delta_lon / 2.0).sin().powi(2);
+        2.0 * Self::EARTH_RADIUS_KM * a.sqrt().asin()
+    }
+}
+
+/// Type that represents a location of a listing.
+///
+/// Besides the name and the strength, a listing can provide a score for the defenses of the
+/// location, where lower means weaker, and its coordinates.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Location {
+    pub name: String,
+    pub strength: Strength,
+    pub score: Option<u32>,
+    pub coordinates: Option<Coordinates>,
+}
+
+impl Location {
+    #[must_use]
+    pub fn is_weak(&self) -> bool {
+        self.strength == Strength::Weak
+    }
+}
+
+impl From<&str> for Location {
+    /// Creates a weak location with the given name and no score or coordinates.
+    fn from(name: &str) -> Self {
+        Location {
+            name: name.to_string(),
+            strength: Strength::Weak,
+            score: None,
+            coordinates: None,
+        }
+    }
+}
+
+/// Parses a listing of locations, one per line.
+///
+/// Every line must contain the name of the location and its strength, separated by a comma,
+/// optionally followed by the score and then by the latitude and longitude. Whitespace around the
+/// fields is ignored and so are blank lines. Each line produces either a
+/// location or the error that prevented parsing it, so a malformed line doesn't hide the rest.
+/// Lines that cannot be decoded are malformed too, but any other read error ends the listing.
+///
+/// # Examples
+/// ```
+///# use evil::location::{Strength, parse_listing};
+/// let listing = "Madrid,strong\nLas Vegas, weak, 12, 36.17, -115.14\nAtlantis\n";
+/// let locations = parse_listing(listing.as_bytes()).collect::<Vec<_>>();
+/// assert_eq!(locations[1].as_ref().unwrap().strength, Strength::Weak);
+/// assert_eq!(locations[1].as_ref().unwrap().score, Some(12));
+/// assert!(locations[2].is_err());
+/// ```
+pub fn parse_listing<R: BufRead>(reader: R) -> impl Iterator<Item = Result<Location, EvilError>> {
+    let mut read_failed = false;
+    reader
+        .lines()
+        .enumerate()
+        .map_while(move |(index, line)| {
+            if read_failed {
+                return None;
+            }
+            let line_number = index + 1;
+            let parsed = match line {
+                Ok(line) if line.trim().is_empty() => return Some(None),
+                Ok(line) => parse_line(&line),
+                Err(err) if err.kind() == io::ErrorKind::InvalidData => Err(err.to_string()),
+                Err(err) => {
+                    read_failed = true;
+                    return Some(Some(Err(EvilError::Io {
+                        kind: err.kind().into(),
+                        reason: format!("line {line_number}: {err}"),
+                    })));
+                }
+            };
+            Some(Some(parsed.map_err(|reason| EvilError::ListingFormat {
+                line: line_number,
+                reason,
+            })))
+        })
+        .flatten()
+}
+
+fn parse_line(line: &str) -> Result<Location, String> {
+    let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+    let (name, strength, score, coordinates) = match fields[..] {
+        [name, strength] => (name, strength, None, None),
+        [name, strength, score] => (name, strength, Some(score), None),
+        [name, strength, score, latitude, longitude] => {
+            (name, strength, Some(score), Some((latitude, longitude)))
+        }
+        [_] => return Err(String::from("Expected name and strength")),
+        [_, _, _, _] => return Err(String::from("Expected latitude and longitude")),
+        _ => return Err(String::from("Too many fields")),
+    };
+    if name.is_empty() {
+        return Err(String::from("Empty name"));
+    }
+    Ok(Location {
+        name: name.to_string(),
+        strength: strength.parse()?,
+        score: score
+            .filter(|score| !score.is_empty())
+            .map(|score| {
+                score
+                    .parse()
+                    .map_err(|_| format!("Invalid score '{score}'"))
+            })
+            .transpose()?,
+        coordinates: coordinates
+            .map(|(latitude, longitude)| parse_coordinates(latitude, longitude))
+            .transpose()?,
+    })
+}
+
+fn parse_coordinates(latitude: &str, longitude: &str) -> Result<Coordinates, String> {
+    let invalid = || format!("Invalid coordinates '{latitude}, {longitude}'");
+    let latitude = latitude.parse::<f64>().map_err(|_| invalid())?;
+    let longitude = longitude.parse::<f64>().map_err(|_| invalid())?;
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return Err(invalid());
+    }
+    Ok(Coordinates {
+        latitude,
+        longitude,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Read};
+
+    use assertables::assert_matches;
+
+    use super::*;
+    use crate::supervillain::IoErrorKind;
+
+    /// Reader that fails every time it is read.
+    struct BrokenReader;
+
+    impl Read for BrokenReader {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::from(io::ErrorKind::PermissionDenied))
+        }
+    }
+
+    fn location(name: &str, strength: Strength) -> Location {
+        Location {
+            strength,
+            ..Location::from(name)
+        }
+    }
+
+    #[test]
+    fn listing_lines_are_parsed_into_locations() {
+        let listing = "Madrid,strong\n  Las Vegas , WEAK \n";
+
+        let locations = parse_listing(listing.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            locations,
+            vec![
+                location("Madrid", Strength::Strong),
+                location("Las Vegas", Strength::Weak)
+            ]
+        );
+    }
+
+    #[test]
+    fn blank_lines_are_skipped() {
+        let listing = "\nMadrid,strong\n   \n";
+
+        let locations = parse_listing(listing.as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(locations.len(), 1);
+    }
+
+    #[test]
+    fn line_without_strength_reports_its_line_number() {
+        let listing = "Madrid,strong\nAtlantis\nNew York,strong";
+
+        let locations = parse_listing(listing.as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(locations.len(), 3);
+        assert_matches!(&locations[1], Err(EvilError::ListingFormat { line: 2, reason }) if reason == "Expected name and strength");
+        assert_eq!(
+            locations[2].as_ref().unwrap(),
+            &location("New York", Strength::Strong)
+        );
+    }
+
+    #[test]
+    fn read_error_is_reported_once_and_ends_the_listing() {
+        let reader = BufReader::new("Madrid,strong\n".as_bytes().chain(BrokenReader));
+
+        let locations = parse_listing(reader).collect::<Vec<_>>();
+
+        assert_eq!(locations.len(), 2);
+        assert_eq!(
+            locations[0].as_ref().unwrap(),
+            &location("Madrid", Strength::Strong)
+        );
+        assert_matches!(
+            &locations[1],
+            Err(EvilError::Io {
+                kind: IoErrorKind::PermissionDenied,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn undecodable_line_is_malformed() {
+        let listing = b"Las \xff Vegas,weak\nTampa,weak\n";
+
+        let locations = parse_listing(&listing[..]).collect::<Vec<_>>();
+
+        assert_matches!(&locations[0], Err(EvilError::ListingFormat { line: 1, .. }));
+        assert!(locations[1].is_ok());
+    }
+
+    #[test]
+    fn line_with_unknown_strength_is_an_error() {
+        let locations = parse_listing("Gotham,medium".as_bytes()).collect::<Vec<_>>();
+
+        assert_matches!(&locations[0], Err(EvilError::ListingFormat { line: 1, reason }) if reason == "Unknown strength 'medium'");
+    }
+
+    #[test]
+    fn line_with_empty_name_is_an_error() {
+        let locations = parse_listing(" ,weak".as_bytes()).collect::<Vec<_>>();
+
+        assert_matches!(&locations[0], Err(EvilError::ListingFormat { line: 1, reason }) if reason == "Empty name");
+    }
+
+    #[test]
+    fn score_and_coordinates_are_optional_fields() {
+        let listing = "Tampa,weak,7\nVilnius,weak,,54.69,25.28\n";
+
+        let locations = parse_listing(listing.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(locations[0].score, Some(7));
+        assert_eq!(locations[0].coordinates, None);
+        assert_eq!(locations[1].score, None);
+        assert_eq!(
+            locations[1].coordinates,
+            Some(Coordinates {
+                latitude: 54.69,
+                longitude: 25.28
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_score_or_coordinates_are_errors() {
+        let listing = "Tampa,weak,high\nVilnius,weak,3,54.69\nGotham,weak,3,95,0\n";
+
+        let locations = parse_listing(listing.as_bytes()).collect::<Vec<_>>();
+
+        assert_matches!(&locations[0], Err(EvilError::ListingFormat { line: 1, reason }) if reason == "Invalid score 'high'");
+        assert_matches!(&locations[1], Err(EvilError::ListingFormat { line: 2, reason }) if reason == "Expected latitude and longitude");
+        assert_matches!(&locations[2], Err(EvilError::ListingFormat { line: 3, reason }) if reason == "Invalid coordinates '95, 0'");
+    }
+
+    #[test]
+    fn distance_between_coordinates_is_great_circle() {
+        let tampa = Coordinates {
+            latitude: 27.9506,
+            longitude: -82.4572,
+        };
+
+        assert!(tampa.distance_km(&tampa) < f64::EPSILON);
+        let pamplona = Coordinates {
+            latitude: 42.8125,
+            longitude: -1.6458,
+        };
+        let distance = tampa.distance_km(&pamplona);
+        assert!(
+            (7200.0..7260.0).contains(&distance),
+            "Distance was {distance}"
+        );
+    }
+}
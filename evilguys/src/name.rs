@@ -0,0 +1,237 @@
+//! Module for parsing the full names of supervillains
+use std::str::FromStr;
+
+use crate::supervillain::EvilError;
+
+/// Titles that can precede the given names. Matched case-insensitively and ignoring a trailing
+/// period.
+const TITLES: [&str; 26] = [
+    "baron",
+    "baroness",
+    "captain",
+    "count",
+    "countess",
+    "dame",
+    "doctor",
+    "dr",
+    "duchess",
+    "duke",
+    "emperor",
+    "empress",
+    "general",
+    "king",
+    "lady",
+    "lord",
+    "madame",
+    "master",
+    "mister",
+    "mr",
+    "mrs",
+    "ms",
+    "prof",
+    "professor",
+    "queen",
+    "sir",
+];
+
+/// Particles that belong to the last name when they precede it, like "von" or "de la".
+/// Matched case-insensitively.
+const PARTICLES: [&str; 23] = [
+    "al", "bin", "da", "das", "de", "del", "della", "den", "der", "des", "di", "do", "dos", "du",
+    "el", "ibn", "la", "le", "st", "ten", "ter", "van", "von",
+];
+
+/// Components of a full name.
+///
+/// The name is split in an optional title, one or more given names (first and middle names), and
+/// a last name that includes any particles that precede it.
+///
+/// # Examples
+/// ```
+///# use evil::name::FullName;
+/// let name: FullName = "Doctor  Victor Werner von Doom".parse().unwrap();
+/// assert_eq!(name.title.as_deref(), Some("Doctor"));
+/// assert_eq!(name.given_names, ["Victor", "Werner"]);
+/// assert_eq!(name.last_name, "von Doom");
+/// assert_eq!(name.first_name(), "Doctor Victor Werner");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FullName {
+    pub title: Option<String>,
+    pub given_names: Vec<String>,
+    pub last_name: String,
+}
+
+impl FullName {
+    /// Returns the title, if any, followed by all the given names, separated by single spaces.
+    #[must_use]
+    pub fn first_name(&self) -> String {
+        self.title
+            .iter()
+            .chain(&self.given_names)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl FromStr for FullName {
+    type Err = EvilError;
+
+    /// Parses a full name.
+    ///
+    /// Words can be separated by any amount of whitespace. The first word is only taken as a
+    /// title if there are at least two more words after it, so "Doctor Doom" has "Doctor" as
+    /// given name.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let words = name.split_whitespace().collect::<Vec<_>>();
+        if words.len() < 2 {
+            return Err(parse_error("Too few arguments"));
+        }
+        if let Some(word) = words.iter().find(|word| !is_valid_word(word)) {
+            return Err(parse_error(&format!("Invalid word '{word}'")));
+        }
+
+        let (title, rest) = match words.split_first() {
+            Some((first, rest)) if rest.len() >= 2 && is_one_of(first, &TITLES) => {
+                (Some((*first).to_string()), rest)
+            }
+            _ => (None, words.as_slice()),
+        };
+        // At least one given name must remain before the last name.
+        let mut last_name_start = rest.len() - 1;
+        while last_name_start > 1 && is_one_of(rest[last_name_start - 1], &PARTICLES) {
+            last_name_start -= 1;
+        }
+        let (given_names, last_name) = rest.split_at(last_name_start);
+
+        Ok(FullName {
+            title,
+            given_names: given_names.iter().map(ToString::to_string).collect(),
+            last_name: last_name.join(" "),
+        })
+    }
+}
+
+fn is_valid_word(word: &str) -> bool {
+    word.chars()
+        .all(|c| c.is_alphabetic() || matches!(c, '-' | '\'' | '’' | '.'))
+}
+
+fn is_one_of(word: &str, candidates: &[&str]) -> bool {
+    let word = word.trim_end_matches('.').to_lowercase();
+    candidates.contains(&word.as_str())
+}
+
+fn parse_error(reason: &str) -> EvilError {
+    EvilError::ParseError {
+        purpose: "full_name".to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertables::assert_matches;
+
+    use super::*;
+
+    fn parse(name: &str) -> FullName {
+        name.parse().expect("Valid name")
+    }
+
+    #[test]
+    fn two_words_are_given_name_and_last_name() {
+        let name = parse("Lex Luthor");
+
+        assert_eq!(
+            name,
+            FullName {
+                title: None,
+                given_names: vec![String::from("Lex")],
+                last_name: String::from("Luthor"),
+            }
+        );
+    }
+
+    #[test]
+    fn middle_names_are_kept_as_given_names() {
+        let name = parse("Victor Werner Doom");
+
+        assert_eq!(name.given_names, ["Victor", "Werner"]);
+        assert_eq!(name.last_name, "Doom");
+    }
+
+    #[test]
+    fn title_is_recognized_before_given_names() {
+        let name = parse("Dr. Julius No");
+
+        assert_eq!(name.title.as_deref(), Some("Dr."));
+        assert_eq!(name.given_names, ["Julius"]);
+        assert_eq!(name.first_name(), "Dr. Julius");
+    }
+
+    #[test]
+    fn title_followed_by_a_single_word_is_the_given_name() {
+        let name = parse("Doctor Doom");
+
+        assert_eq!(name.title, None);
+        assert_eq!(name.given_names, ["Doctor"]);
+        assert_eq!(name.last_name, "Doom");
+    }
+
+    #[test]
+    fn particles_belong_to_the_last_name() {
+        let name = parse("Doctor Victor Von Doom");
+
+        assert_eq!(name.title.as_deref(), Some("Doctor"));
+        assert_eq!(name.given_names, ["Victor"]);
+        assert_eq!(name.last_name, "Von Doom");
+    }
+
+    #[test]
+    fn multiple_particles_belong_to_the_last_name() {
+        let name = parse("Cruella de la Vil");
+
+        assert_eq!(name.given_names, ["Cruella"]);
+        assert_eq!(name.last_name, "de la Vil");
+    }
+
+    #[test]
+    fn particle_in_first_position_is_a_given_name() {
+        let name = parse("Van Helsing");
+
+        assert_eq!(name.given_names, ["Van"]);
+        assert_eq!(name.last_name, "Helsing");
+    }
+
+    #[test]
+    fn multiple_whitespace_is_ignored() {
+        let name = parse("  Darth \t  Vader\n");
+
+        assert_eq!(name.given_names, ["Darth"]);
+        assert_eq!(name.last_name, "Vader");
+    }
+
+    #[test]
+    fn non_ascii_names_are_supported() {
+        let name = parse("Григорий Распутин");
+
+        assert_eq!(name.given_names, ["Григорий"]);
+        assert_eq!(name.last_name, "Распутин");
+    }
+
+    #[test]
+    fn single_word_is_too_few_arguments() {
+        let result = "Voldemort".parse::<FullName>();
+
+        assert_matches!(result, Err(EvilError::ParseError { purpose, reason }) if purpose == "full_name" && reason == "Too few arguments");
+    }
+
+    #[test]
+    fn words_with_digits_are_invalid() {
+        let result = "Agent 47".parse::<FullName>();
+
+        assert_matches!(result, Err(EvilError::ParseError { reason, .. }) if reason == "Invalid word '47'");
+    }
+}
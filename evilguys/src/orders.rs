@@ -0,0 +1,985 @@
+//! Module for the orders that supervillains give to their henchmen
+use std::{
+    fmt::{self, Display},
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
+
+use chrono::NaiveDate;
+use serde_json::json;
+
+use crate::{henchman::HenchmanId, supervillain::EvilError};
+
+/// How urgent an order is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Critical,
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(priority: &str) -> Result<Self, Self::Err> {
+        match priority.to_lowercase().as_str() {
+            "low" => Ok(Priority::Low),
+            "normal" => Ok(Priority::Normal),
+            "high" => Ok(Priority::High),
+            "critical" => Ok(Priority::Critical),
+            _ => Err(format!("Unknown priority '{priority}'")),
+        }
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let priority = match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+            Priority::Critical => "critical",
+        };
+        f.write_str(priority)
+    }
+}
+
+/// Progress of an order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum OrderStatus {
+    #[default]
+    Pending,
+    InProgress,
+    Done,
+    Cancelled,
+}
+
+impl FromStr for OrderStatus {
+    type Err = String;
+
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        match status.to_lowercase().as_str() {
+            "pending" => Ok(OrderStatus::Pending),
+            "in-progress" => Ok(OrderStatus::InProgress),
+            "done" => Ok(OrderStatus::Done),
+            "cancelled" => Ok(OrderStatus::Cancelled),
+            _ => Err(format!("Unknown status '{status}'")),
+        }
+    }
+}
+
+impl Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            OrderStatus::Pending => "pending",
+            OrderStatus::InProgress => "in-progress",
+            OrderStatus::Done => "done",
+            OrderStatus::Cancelled => "cancelled",
+        };
+        f.write_str(status)
+    }
+}
+
+/// Order given by a supervillain.
+///
+/// Plain strings convert into pending orders with normal priority, no deadline and nobody
+/// assigned, so they can be used wherever an order is expected.
+///
+/// # Examples
+/// ```
+///# use evil::orders::{Order, Priority};
+/// let order = Order {
+///     priority: Priority::High,
+///     assignee: Some(7),
+///     ..Order::from("Fight enemies")
+/// };
+/// assert_eq!(order.to_string(), "Fight enemies [priority: high, assignee: 7]");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Order {
+    pub description: String,
+    pub priority: Priority,
+    pub deadline: Option<NaiveDate>,
+    pub assignee: Option<HenchmanId>,
+    pub status: OrderStatus,
+}
+
+impl Order {
+    #[must_use]
+    pub fn new(description: &str) -> Self {
+        Order {
+            description: description.to_string(),
+            ..Order::default()
+        }
+    }
+
+    /// Returns the attributes that differ from the defaults as `name: value` pairs.
+    fn attributes(&self) -> Vec<String> {
+        let mut attributes = vec![];
+        if self.priority != Priority::default() {
+            attributes.push(format!("priority: {}", self.priority));
+        }
+        if let Some(deadline) = self.deadline {
+            attributes.push(format!("deadline: {deadline}"));
+        }
+        if let Some(assignee) = self.assignee {
+            attributes.push(format!("assignee: {assignee}"));
+        }
+        if self.status != OrderStatus::default() {
+            attributes.push(format!("status: {}", self.status));
+        }
+        attributes
+    }
+}
+
+impl From<String> for Order {
+    fn from(description: String) -> Self {
+        Order {
+            description,
+            ..Order::default()
+        }
+    }
+}
+
+impl From<&str> for Order {
+    fn from(description: &str) -> Self {
+        Order::new(description)
+    }
+}
+
+impl Display for Order {
+    /// Writes the description followed by the attributes that differ from the defaults in
+    /// square brackets, if any.
+    ///
+    /// The description is written as is, unless it has line breaks or wouldn't be read back
+    /// the same, like one that ends in something that looks like attributes. Then it is written
+    /// in double quotes, escaping backslashes, quotes and line breaks with a backslash.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let attributes = self.attributes();
+        let block = if attributes.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", attributes.join(", "))
+        };
+        let plain = format!("{}{block}", self.description);
+        if !self.description.contains(['\n', '\r'])
+            && plain.parse::<Order>().is_ok_and(|parsed| parsed == *self)
+        {
+            return f.write_str(&plain);
+        }
+        f.write_str("\"")?;
+        for c in self.description.chars() {
+            match c {
+                '\\' => f.write_str("\\\\")?,
+                '"' => f.write_str("\\\"")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                _ => write!(f, "{c}")?,
+            }
+        }
+        write!(f, "\"{block}")
+    }
+}
+
+impl FromStr for Order {
+    type Err = String;
+
+    /// Parses an order as written by its `Display` implementation.
+    ///
+    /// A block in square brackets at the end is only taken as attributes if all its entries are
+    /// `name: value` pairs with known names, so descriptions written by hand can still contain
+    /// brackets. A description in double quotes is unescaped and kept whole, even if it is
+    /// empty or has spaces at its ends. Any other one is trimmed and must not be empty.
+    fn from_str(order: &str) -> Result<Self, Self::Err> {
+        let (description, attributes) = split_order(order.trim())?;
+
+        let mut parsed = Order::from(description);
+        for (name, value) in attributes {
+            match name {
+                "priority" => parsed.priority = value.parse()?,
+                "deadline" => {
+                    parsed.deadline = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid deadline '{value}'"))?,
+                    );
+                }
+                "assignee" => {
+                    parsed.assignee = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid assignee '{value}'"))?,
+                    );
+                }
+                _ => parsed.status = value.parse()?,
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// Attributes of an order as `name: value` pairs.
+type Attributes<'a> = Vec<(&'a str, &'a str)>;
+
+/// Splits an order in its description and its attributes.
+fn split_order(order: &str) -> Result<(String, Attributes<'_>), String> {
+    if let Some((description, rest)) = order.strip_prefix('"').and_then(unquote) {
+        if rest.is_empty() {
+            return Ok((description, vec![]));
+        }
+        if let Some(attributes) = rest.strip_prefix(' ').and_then(attribute_block) {
+            return Ok((description, attributes));
+        }
+    }
+    let (description, attributes) = order
+        .match_indices(" [")
+        .find_map(|(index, _)| {
+            attribute_block(&order[index + 1..]).map(|attributes| (&order[..index], attributes))
+        })
+        .unwrap_or((order, vec![]));
+    if description.is_empty() {
+        return Err(String::from("Empty description"));
+    }
+    Ok((description.to_string(), attributes))
+}
+
+/// Unescapes a description up to its closing double quote and returns it with the text after
+/// the quote. Returns `None` if the quote isn't closed or has an invalid escape.
+fn unquote(quoted: &str) -> Option<(String, &str)> {
+    let mut description = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next()?.1 {
+                'n' => description.push('\n'),
+                'r' => description.push('\r'),
+                escaped @ ('\\' | '"') => description.push(escaped),
+                _ => return None,
+            },
+            '"' => return Some((description, &quoted[index + 1..])),
+            _ => description.push(c),
+        }
+    }
+    None
+}
+
+/// Returns the attributes of a block in square brackets, if it is one.
+fn attribute_block(block: &str) -> Option<Attributes<'_>> {
+    block
+        .strip_prefix('[')
+        .and_then(|block| block.strip_suffix(']'))
+        .and_then(attribute_pairs)
+}
+
+/// Splits a block of attributes in `name: value` pairs. Returns `None` if any entry isn't a pair
+/// with a known name.
+fn attribute_pairs(block: &str) -> Option<Attributes<'_>> {
+    block
+        .split(", ")
+        .map(|entry| {
+            entry
+                .split_once(": ")
+                .filter(|(name, _)| ORDER_ATTRIBUTES.contains(name))
+        })
+        .collect()
+}
+
+/// Names of the attributes that can follow the description of an order.
+const ORDER_ATTRIBUTES: [&str; 4] = ["priority", "deadline", "assignee", "status"];
+
+/// Parses orders written in plain text, checking that they were given by `leader`.
+///
+/// The first non blank line must be the "I, <leader>, as your leader, tell you to:" header and
+/// every other non blank line an order preceded by "- ". The header can be repeated, like in
+/// files the orders have been appended to, as long as it is from the same leader.
+///
+/// # Errors
+/// - `EvilError::MalformedOrders` with the line number of the first problem found, including a
+///   missing header or one from a different leader.
+/// - `EvilError::Io` if the orders cannot be read.
+///
+/// # Examples
+/// ```
+///# use evil::orders::{Priority, parse_orders};
+/// let written = "I, Lex Luthor, as your leader, tell you to:\n- Fight enemies [priority: high]\n";
+/// let orders = parse_orders(written.as_bytes(), "Lex Luthor").unwrap();
+/// assert_eq!(orders[0].description, "Fight enemies");
+/// assert_eq!(orders[0].priority, Priority::High);
+/// ```
+pub fn parse_orders<R: BufRead>(reader: R, leader: &str) -> Result<Vec<Order>, EvilError> {
+    let mut orders = vec![];
+    let mut header_found = false;
+    let mut last_line = 0;
+    for (index, line) in reader.lines().enumerate() {
+        last_line = index + 1;
+        let line = line.map_err(|err| EvilError::Io {
+            kind: err.kind().into(),
+            reason: format!("line {last_line}: {err}"),
+        })?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let parsed = if header_found && !line.starts_with(HEADER_START) {
+            parse_order_line(line).map(|order| orders.push(order))
+        } else {
+            check_header(line, leader)
+        };
+        parsed.map_err(|reason| malformed_orders(last_line, reason))?;
+        header_found = true;
+    }
+    if !header_found {
+        return Err(malformed_orders(
+            last_line + 1,
+            String::from("Missing leader header"),
+        ));
+    }
+    Ok(orders)
+}
+
+const HEADER_START: &str = "I, ";
+const HEADER_END: &str = ", as your leader, tell you to:";
+
+fn check_header(line: &str, leader: &str) -> Result<(), String> {
+    let Some(found) = line
+        .strip_prefix(HEADER_START)
+        .and_then(|rest| rest.strip_suffix(HEADER_END))
+    else {
+        return Err(String::from("Expected leader header"));
+    };
+    if found == leader {
+        Ok(())
+    } else {
+        Err(format!("Unexpected leader '{found}'"))
+    }
+}
+
+fn parse_order_line(line: &str) -> Result<Order, String> {
+    line.strip_prefix("- ")
+        .ok_or_else(|| String::from("Expected order"))?
+        .parse()
+}
+
+fn malformed_orders(line: usize, reason: String) -> EvilError {
+    EvilError::MalformedOrders { line, reason }
+}
+
+/// Format used to write a list of orders.
+///
+/// The plain text format is the default and keeps the classic "I, <name>, as your leader, tell
+/// you to:" header. JSON and CSV are meant for tools that consume the orders. JSON writes the
+/// orders as they are serialized with the `serde` feature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OrdersFormat {
+    #[default]
+    PlainText,
+    Markdown,
+    Json,
+    Csv,
+}
+
+/// How to treat an existing file when writing orders to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WriteMode {
+    /// Replaces the file atomically: orders are written to a temporary file in the same
+    /// directory that is renamed over the original only when all of them have been written.
+    #[default]
+    Overwrite,
+    /// Adds the orders at the end of the file, creating it if needed. The header is only written
+    /// if the file is empty. JSON orders can only be appended to an empty file.
+    Append,
+}
+
+/// Options used to write orders to a file.
+///
+/// # Examples
+/// ```
+///# use evil::orders::{OrdersFormat, WriteMode, WriteOptions};
+/// let options = WriteOptions {
+///     mode: WriteMode::Append,
+///     create_dirs: true,
+///     ..WriteOptions::default()
+/// };
+/// assert_eq!(options.format, OrdersFormat::PlainText);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WriteOptions {
+    pub format: OrdersFormat,
+    pub mode: WriteMode,
+    /// Creates the missing parent directories of the file.
+    pub create_dirs: bool,
+}
+
+impl OrdersFormat {
+    /// Writes the orders given by `leader` and returns the number of orders written.
+    ///
+    /// # Errors
+    /// - `io::Error` if the orders cannot be written.
+    ///
+    /// # Examples
+    /// ```
+    ///# use evil::orders::OrdersFormat;
+    /// let mut output = vec![];
+    /// let written = OrdersFormat::Csv
+    ///     .write_orders(&mut output, "Lex Luthor", ["Fight enemies"])
+    ///     .unwrap();
+    /// assert_eq!(written, 1);
+    /// assert_eq!(
+    ///     output,
+    ///     b"leader,order,priority,deadline,assignee,status\nLex Luthor,Fight enemies,normal,,,pending\n"
+    /// );
+    /// ```
+    pub fn write_orders<W, I>(self, out: &mut W, leader: &str, orders: I) -> io::Result<usize>
+    where
+        W: Write + ?Sized,
+        I: IntoIterator,
+        I::Item: Into<Order>,
+    {
+        self.write(out, leader, orders, true)
+    }
+
+    /// Writes the orders given by `leader` after others already written in this format, so the
+    /// header isn't repeated, and returns the number of orders written.
+    ///
+    /// # Errors
+    /// - `io::Error` if the orders cannot be written.
+    /// - `io::Error` with `io::ErrorKind::Unsupported` for JSON, since a JSON document cannot be
+    ///   extended by appending to it. Nothing is written then.
+    ///
+    /// # Examples
+    /// ```
+    ///# use evil::orders::OrdersFormat;
+    /// let mut output = vec![];
+    /// OrdersFormat::PlainText
+    ///     .write_orders(&mut output, "Lex Luthor", ["Fight enemies"])
+    ///     .unwrap();
+    /// OrdersFormat::PlainText
+    ///     .append_orders(&mut output, "Lex Luthor", ["Rest"])
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     output,
+    ///     b"I, Lex Luthor, as your leader, tell you to:\n- Fight enemies\n- Rest\n"
+    /// );
+    /// ```
+    pub fn append_orders<W, I>(self, out: &mut W, leader: &str, orders: I) -> io::Result<usize>
+    where
+        W: Write + ?Sized,
+        I: IntoIterator,
+        I::Item: Into<Order>,
+    {
+        if self == OrdersFormat::Json {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "JSON orders cannot be appended",
+            ));
+        }
+        self.write(out, leader, orders, false)
+    }
+
+    fn write<W, I>(self, out: &mut W, leader: &str, orders: I, header: bool) -> io::Result<usize>
+    where
+        W: Write + ?Sized,
+        I: IntoIterator,
+        I::Item: Into<Order>,
+    {
+        let orders = orders.into_iter().map(Into::into);
+        let mut orders_written = 0;
+        match self {
+            OrdersFormat::PlainText => {
+                if header {
+                    writeln!(out, "{HEADER_START}{leader}{HEADER_END}")?;
+                }
+                for order in orders {
+                    writeln!(out, "- {order}")?;
+                    orders_written += 1;
+                }
+            }
+            OrdersFormat::Markdown => {
+                if header {
+                    writeln!(out, "# Orders from {leader}")?;
+                    writeln!(out)?;
+                }
+                for order in orders {
+                    orders_written += 1;
+                    writeln!(out, "{orders_written}. {order}")?;
+                }
+            }
+            OrdersFormat::Json => {
+                let orders = orders.map(|order| json_order(&order)).collect::<Vec<_>>();
+                orders_written = orders.len();
+                serde_json::to_writer(&mut *out, &json!({ "leader": leader, "orders": orders }))?;
+                writeln!(out)?;
+            }
+            OrdersFormat::Csv => {
+                if header {
+                    writeln!(out, "leader,order,priority,deadline,assignee,status")?;
+                }
+                for order in orders {
+                    writeln!(
+                        out,
+                        "{},{},{},{},{},{}",
+                        csv_field(leader),
+                        csv_field(&order.description),
+                        order.priority,
+                        order
+                            .deadline
+                            .map(|date| date.to_string())
+                            .unwrap_or_default(),
+                        order.assignee.map(|id| id.to_string()).unwrap_or_default(),
+                        order.status
+                    )?;
+                    orders_written += 1;
+                }
+            }
+        }
+        Ok(orders_written)
+    }
+}
+
+/// Returns the order as it is serialized with the `serde` feature, which uses the same names
+/// as `Display` for the priority and the status.
+fn json_order(order: &Order) -> serde_json::Value {
+    json!({
+        "description": order.description,
+        "priority": order.priority.to_string(),
+        "deadline": order.deadline.map(|date| date.to_string()),
+        "assignee": order.assignee,
+        "status": order.status.to_string(),
+    })
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertables::{assert_matches, assert_ok_eq_x};
+
+    use super::*;
+
+    fn written(format: OrdersFormat, orders: &[Order]) -> String {
+        let mut output = vec![];
+        assert_ok_eq_x!(
+            format.write_orders(&mut output, "Lex Luthor", orders.iter().cloned()),
+            orders.len()
+        );
+        String::from_utf8(output).expect("Valid UTF-8")
+    }
+
+    fn urgent_order() -> Order {
+        Order {
+            priority: Priority::Critical,
+            deadline: NaiveDate::from_ymd_opt(2026, 10, 31),
+            assignee: Some(3),
+            status: OrderStatus::InProgress,
+            ..Order::from("Fight enemies")
+        }
+    }
+
+    #[test]
+    fn default_format_is_plain_text() {
+        assert_eq!(OrdersFormat::default(), OrdersFormat::PlainText);
+    }
+
+    #[test]
+    fn order_from_string_has_default_attributes() {
+        let order = Order::from(String::from("Build headquarters"));
+
+        assert_eq!(order.description, "Build headquarters");
+        assert_eq!(order.priority, Priority::Normal);
+        assert_eq!(order.deadline, None);
+        assert_eq!(order.assignee, None);
+        assert_eq!(order.status, OrderStatus::Pending);
+    }
+
+    #[test]
+    fn order_display_includes_only_non_default_attributes() {
+        assert_eq!(
+            Order::from("Build headquarters").to_string(),
+            "Build headquarters"
+        );
+        assert_eq!(
+            urgent_order().to_string(),
+            "Fight enemies [priority: critical, deadline: 2026-10-31, assignee: 3, status: in-progress]"
+        );
+    }
+
+    #[test]
+    fn priorities_and_statuses_parse_their_display() {
+        for priority in [
+            Priority::Low,
+            Priority::Normal,
+            Priority::High,
+            Priority::Critical,
+        ] {
+            assert_eq!(priority.to_string().parse(), Ok(priority));
+        }
+        for status in [
+            OrderStatus::Pending,
+            OrderStatus::InProgress,
+            OrderStatus::Done,
+            OrderStatus::Cancelled,
+        ] {
+            assert_eq!(status.to_string().parse(), Ok(status));
+        }
+        assert_matches!("urgent".parse::<Priority>(), Err(reason) if reason == "Unknown priority 'urgent'");
+    }
+
+    #[test]
+    fn order_parses_its_display() {
+        let order = urgent_order();
+
+        assert_eq!(order.to_string().parse(), Ok(order));
+    }
+
+    #[test]
+    fn order_with_unknown_bracket_block_keeps_it_in_description() {
+        let order = "Steal the moon [again]".parse::<Order>();
+
+        assert_eq!(order, Ok(Order::from("Steal the moon [again]")));
+    }
+
+    #[test]
+    fn order_with_tricky_description_parses_its_display() {
+        for description in [
+            "Steal the moon [priority: high]",
+            "Fight enemies\n- Rest",
+            "Escape with \\[brackets\\] and \r returns",
+            "\"Quoted\"",
+        ] {
+            let order = Order {
+                assignee: Some(3),
+                ..Order::from(description)
+            };
+
+            let displayed = order.to_string();
+
+            assert!(!displayed.contains('\n'), "Multiline '{displayed}'");
+            assert_eq!(displayed.parse(), Ok(order));
+            assert_eq!(
+                Order::from(description).to_string().parse(),
+                Ok(Order::from(description))
+            );
+        }
+    }
+
+    #[test]
+    fn order_display_keeps_descriptions_that_read_back_the_same() {
+        let order = Order {
+            priority: Priority::High,
+            ..Order::from(r"Steal the moon [again] from C:\lair")
+        };
+
+        let displayed = order.to_string();
+
+        assert_eq!(
+            displayed,
+            r"Steal the moon [again] from C:\lair [priority: high]"
+        );
+        assert_eq!(displayed.parse(), Ok(order));
+    }
+
+    #[test]
+    fn order_display_quotes_descriptions_that_look_like_attributes() {
+        let order = Order::from(r#"Steal the "moon" [priority: high]"#);
+
+        let displayed = order.to_string();
+
+        assert_eq!(displayed, r#""Steal the \"moon\" [priority: high]""#);
+        assert_eq!(displayed.parse(), Ok(order));
+    }
+
+    #[test]
+    fn order_with_unclosed_quote_keeps_it_in_description() {
+        for description in [
+            r#""Fight enemies"#,
+            r#""Fight \enemies""#,
+            r#""Fight" enemies"#,
+        ] {
+            assert_eq!(description.parse(), Ok(Order::from(description)));
+            assert_eq!(Order::from(description).to_string(), description);
+        }
+    }
+
+    #[test]
+    fn written_plain_text_orders_with_tricky_descriptions_are_parsed_back() {
+        let orders = vec![
+            Order::from("Build headquarters\n- Fight enemies"),
+            Order::from("Steal the moon [status: done]"),
+        ];
+        let output = written(OrdersFormat::PlainText, &orders);
+
+        let parsed = parse_orders(output.as_bytes(), "Lex Luthor");
+
+        assert_eq!(parsed.ok(), Some(orders));
+    }
+
+    #[test]
+    fn written_plain_text_orders_with_empty_or_padded_descriptions_are_parsed_back() {
+        let orders = vec![
+            Order::from(""),
+            Order {
+                priority: Priority::Low,
+                ..Order::from("  padded  ")
+            },
+        ];
+        let output = written(OrdersFormat::PlainText, &orders);
+
+        let parsed = parse_orders(output.as_bytes(), "Lex Luthor");
+
+        assert_eq!(parsed.ok(), Some(orders));
+        assert_eq!(
+            output,
+            concat!(
+                "I, Lex Luthor, as your leader, tell you to:\n",
+                "- \"\"\n",
+                "- \"  padded  \" [priority: low]\n"
+            )
+        );
+    }
+
+    #[test]
+    fn order_without_description_is_an_error() {
+        assert_eq!(
+            "[priority: high]".parse::<Order>(),
+            Ok(Order::from("[priority: high]"))
+        );
+        assert_eq!(
+            "  ".parse::<Order>(),
+            Err(String::from("Empty description"))
+        );
+    }
+
+    #[test]
+    fn order_with_invalid_attribute_value_is_an_error() {
+        let order = "Fight enemies [deadline: tomorrow]".parse::<Order>();
+
+        assert_eq!(order, Err(String::from("Invalid deadline 'tomorrow'")));
+    }
+
+    #[test]
+    fn written_plain_text_orders_are_parsed_back() {
+        let orders = vec![Order::from("Build headquarters"), urgent_order()];
+        let output = written(OrdersFormat::PlainText, &orders);
+
+        let parsed = parse_orders(output.as_bytes(), "Lex Luthor");
+
+        assert_eq!(parsed.ok(), Some(orders));
+    }
+
+    #[test]
+    fn orders_from_another_leader_are_rejected() {
+        let output = written(OrdersFormat::PlainText, &[Order::from("Fight enemies")]);
+
+        let parsed = parse_orders(output.as_bytes(), "Gru");
+
+        assert_matches!(parsed, Err(EvilError::MalformedOrders { line: 1, reason }) if reason == "Unexpected leader 'Lex Luthor'");
+    }
+
+    #[test]
+    fn orders_without_header_are_rejected() {
+        assert_matches!(
+            parse_orders("- Fight enemies\n".as_bytes(), "Lex Luthor"),
+            Err(EvilError::MalformedOrders { line: 1, reason }) if reason == "Expected leader header"
+        );
+        assert_matches!(
+            parse_orders("\n".as_bytes(), "Lex Luthor"),
+            Err(EvilError::MalformedOrders { line: 2, reason }) if reason == "Missing leader header"
+        );
+    }
+
+    #[test]
+    fn appended_orders_do_not_repeat_the_header() {
+        for (format, expected) in [
+            (
+                OrdersFormat::PlainText,
+                "I, Lex Luthor, as your leader, tell you to:\n- Fight enemies\n- Rest\n",
+            ),
+            (
+                OrdersFormat::Markdown,
+                "# Orders from Lex Luthor\n\n1. Fight enemies\n1. Rest\n",
+            ),
+            (
+                OrdersFormat::Csv,
+                "leader,order,priority,deadline,assignee,status\nLex Luthor,Fight enemies,normal,,,pending\nLex Luthor,Rest,normal,,,pending\n",
+            ),
+        ] {
+            let mut output = vec![];
+
+            assert_ok_eq_x!(
+                format.write_orders(&mut output, "Lex Luthor", ["Fight enemies"]),
+                1
+            );
+            assert_ok_eq_x!(format.append_orders(&mut output, "Lex Luthor", ["Rest"]), 1);
+
+            assert_eq!(String::from_utf8(output).ok().as_deref(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn json_orders_cannot_be_appended() {
+        let mut output = vec![];
+
+        let result = OrdersFormat::Json.append_orders(&mut output, "Lex Luthor", ["Rest"]);
+
+        assert_matches!(result, Err(err) if err.kind() == io::ErrorKind::Unsupported);
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn repeated_headers_from_the_same_leader_are_skipped() {
+        let written = concat!(
+            "I, Lex Luthor, as your leader, tell you to:\n",
+            "- Build headquarters\n",
+            "I, Lex Luthor, as your leader, tell you to:\n",
+            "- Fight enemies\n",
+            "I, Gru, as your leader, tell you to:\n",
+        );
+
+        assert_matches!(
+            parse_orders(written.as_bytes(), "Lex Luthor"),
+            Err(EvilError::MalformedOrders { line: 5, reason }) if reason == "Unexpected leader 'Gru'"
+        );
+        assert_eq!(
+            parse_orders(
+                &written.as_bytes()[..written.rfind("I, Gru").unwrap()],
+                "Lex Luthor"
+            )
+            .ok(),
+            Some(vec![
+                Order::from("Build headquarters"),
+                Order::from("Fight enemies")
+            ])
+        );
+    }
+
+    #[test]
+    fn line_without_bullet_reports_its_line_number() {
+        let written = concat!(
+            "I, Lex Luthor, as your leader, tell you to:\n",
+            "- Build headquarters\n",
+            "\n",
+            "Fight enemies\n"
+        );
+
+        assert_matches!(
+            parse_orders(written.as_bytes(), "Lex Luthor"),
+            Err(EvilError::MalformedOrders { line: 4, reason }) if reason == "Expected order"
+        );
+    }
+
+    #[test]
+    fn plain_text_has_leader_header_and_bullets() {
+        let output = written(
+            OrdersFormat::PlainText,
+            &[Order::from("Build headquarters"), urgent_order()],
+        );
+
+        assert_eq!(
+            output,
+            concat!(
+                "I, Lex Luthor, as your leader, tell you to:\n",
+                "- Build headquarters\n",
+                "- Fight enemies [priority: critical, deadline: 2026-10-31, assignee: 3, status: in-progress]\n"
+            )
+        );
+    }
+
+    #[test]
+    fn markdown_has_title_and_numbered_list() {
+        let output = written(
+            OrdersFormat::Markdown,
+            &[
+                Order::from("Build headquarters"),
+                Order::from("Fight enemies"),
+            ],
+        );
+
+        assert_eq!(
+            output,
+            concat!(
+                "# Orders from Lex Luthor\n",
+                "\n",
+                "1. Build headquarters\n",
+                "2. Fight enemies\n"
+            )
+        );
+    }
+
+    #[test]
+    fn json_has_leader_and_orders() {
+        let output = written(OrdersFormat::Json, &[urgent_order()]);
+
+        let value: serde_json::Value = serde_json::from_str(&output).expect("Valid JSON");
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "leader": "Lex Luthor",
+                "orders": [{
+                    "description": "Fight enemies",
+                    "priority": "critical",
+                    "deadline": "2026-10-31",
+                    "assignee": 3,
+                    "status": "in-progress",
+                }]
+            })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_orders_are_serialized_orders() {
+        let orders = [urgent_order(), Order::from("Build headquarters")];
+        let output = written(OrdersFormat::Json, &orders);
+
+        let value: serde_json::Value = serde_json::from_str(&output).expect("Valid JSON");
+        for (index, order) in orders.into_iter().enumerate() {
+            assert_eq!(
+                value["orders"][index],
+                serde_json::to_value(&order).expect("Serializable order")
+            );
+            assert_eq!(
+                serde_json::from_value::<Order>(value["orders"][index].clone()).ok(),
+                Some(order)
+            );
+        }
+    }
+
+    #[test]
+    fn csv_quotes_fields_with_separators() {
+        let output = written(
+            OrdersFormat::Csv,
+            &[
+                Order::from("Fight enemies, then rest"),
+                Order {
+                    assignee: Some(3),
+                    ..Order::from("Say \"hi\"")
+                },
+            ],
+        );
+
+        assert_eq!(
+            output,
+            concat!(
+                "leader,order,priority,deadline,assignee,status\n",
+                "Lex Luthor,\"Fight enemies, then rest\",normal,,,pending\n",
+                "Lex Luthor,\"Say \"\"hi\"\"\",normal,,3,pending\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! Module for the plans that supervillains come up with
+use std::fmt::{self, Display};
+
+#[cfg(test)]
+use mockall::automock;
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+use crate::location::Location;
+
+/// Goals used when there are no weak locations to aim for.
+const GOALS: [&str; 4] = [
+    "Take over the world",
+    "Steal the moon",
+    "Hold the world for ransom",
+    "Become the supreme ruler of everything",
+];
+
+/// Goals aimed at a location. `{location}` is replaced with its name.
+const LOCATION_GOALS: [&str; 3] = [
+    "Conquer {location}",
+    "Take {location} and then the world",
+    "Turn {location} into the capital of the new world order",
+];
+
+/// Steps for each weak location. `{location}` is replaced with its name.
+const LOCATION_STEPS: [&str; 4] = [
+    "Build a secret headquarters near {location}",
+    "Infiltrate {location}",
+    "Sabotage the defenses of {location}",
+    "Scout {location} for weak spots",
+];
+
+/// Steps for the henchmen. `{count}` is replaced with their number.
+const HENCHMEN_STEPS: [&str; 3] = [
+    "Send {count} henchmen to fight the enemies",
+    "Train {count} henchmen for the hard things",
+    "Split {count} henchmen into strike teams",
+];
+
+/// Steps for the gadgets. `{count}` is replaced with their number.
+const GADGET_STEPS: [&str; 3] = [
+    "Deploy {count} gadgets",
+    "Upgrade {count} gadgets to doomsday level",
+    "Hide {count} gadgets in plain sight",
+];
+
+/// Steps for the sidekick.
+const SIDEKICK_STEPS: [&str; 3] = [
+    "Tell the sidekick the secret plan",
+    "Have the sidekick find more weak targets",
+    "Make sure the sidekick doesn't betray us",
+];
+
+/// Steps used when there are no resources at all.
+const BOOTSTRAP_STEPS: [&str; 3] = [
+    "Recruit henchmen",
+    "Build a secret lair",
+    "Acquire some gadgets",
+];
+
+/// Maximum number of weak locations that get their own step.
+const MAX_LOCATION_STEPS: usize = 3;
+
+/// Resources available to carry out a plan.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Resources {
+    pub weak_locations: Vec<Location>,
+    pub henchmen: usize,
+    pub gadgets: usize,
+    pub sidekick: bool,
+}
+
+/// Plan with a goal and the steps to achieve it, in order. The goal isn't one of the steps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Plan {
+    pub goal: String,
+    pub steps: Vec<String>,
+}
+
+impl Display for Plan {
+    /// Writes the goal followed by the numbered steps, one per line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}!", self.goal)?;
+        for (index, step) in self.steps.iter().enumerate() {
+            write!(f, "\n{}. {step}", index + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Trait for anything able to come up with plans.
+#[cfg_attr(test, automock)]
+pub trait Planner {
+    fn plan(&mut self, resources: &Resources) -> Plan;
+}
+
+/// Planner that fills templates with the available resources.
+///
+/// The templates are picked with a seeded random generator, so the same seed and resources
+/// always produce the same plan.
+///
+/// # Examples
+/// ```
+///# use evil::plan::{Planner, Resources, TemplatePlanner};
+/// let resources = Resources {
+///     henchmen: 3,
+///     ..Resources::default()
+/// };
+/// let plan = TemplatePlanner::new(7).plan(&resources);
+/// assert_eq!(plan, TemplatePlanner::new(7).plan(&resources));
+/// assert!(plan.steps[0].contains("3 henchmen"));
+/// ```
+pub struct TemplatePlanner {
+    rng: StdRng,
+}
+
+impl TemplatePlanner {
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        TemplatePlanner {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    fn pick(&mut self, templates: &[&'static str]) -> &'static str {
+        templates
+            .choose(&mut self.rng)
+            .expect("Templates are never empty")
+    }
+}
+
+impl Default for TemplatePlanner {
+    /// Creates a planner seeded from the operating system, so plans differ on every run.
+    fn default() -> Self {
+        TemplatePlanner::new(rand::rng().random())
+    }
+}
+
+impl Planner for TemplatePlanner {
+    fn plan(&mut self, resources: &Resources) -> Plan {
+        let goal = match resources.weak_locations.choose(&mut self.rng) {
+            Some(location) => self
+                .pick(&LOCATION_GOALS)
+                .replace("{location}", &location.name),
+            None => self.pick(&GOALS).to_string(),
+        };
+
+        let mut steps = vec![];
+        if resources.sidekick {
+            steps.push(self.pick(&SIDEKICK_STEPS).to_string());
+        }
+        for location in resources.weak_locations.iter().take(MAX_LOCATION_STEPS) {
+            steps.push(
+                self.pick(&LOCATION_STEPS)
+                    .replace("{location}", &location.name),
+            );
+        }
+        if resources.henchmen > 0 {
+            steps.push(
+                self.pick(&HENCHMEN_STEPS)
+                    .replace("{count}", &resources.henchmen.to_string()),
+            );
+        }
+        if resources.gadgets > 0 {
+            steps.push(
+                self.pick(&GADGET_STEPS)
+                    .replace("{count}", &resources.gadgets.to_string()),
+            );
+        }
+        if steps.is_empty() {
+            steps.extend(BOOTSTRAP_STEPS.iter().map(ToString::to_string));
+        }
+
+        Plan { goal, steps }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weak(name: &str) -> Location {
+        Location::from(name)
+    }
+
+    fn all_resources() -> Resources {
+        Resources {
+            weak_locations: vec![weak("Las Vegas"), weak("Tampa")],
+            henchmen: 5,
+            gadgets: 2,
+            sidekick: true,
+        }
+    }
+
+    #[test]
+    fn same_seed_produces_same_plan() {
+        let resources = all_resources();
+
+        let plan = TemplatePlanner::new(42).plan(&resources);
+
+        assert_eq!(plan, TemplatePlanner::new(42).plan(&resources));
+    }
+
+    #[test]
+    fn plan_has_a_step_per_resource() {
+        let plan = TemplatePlanner::new(42).plan(&all_resources());
+
+        assert_eq!(plan.steps.len(), 5);
+        assert!(SIDEKICK_STEPS.contains(&plan.steps[0].as_str()));
+        assert!(plan.steps[1].contains("Las Vegas"));
+        assert!(plan.steps[2].contains("Tampa"));
+        assert!(plan.steps[3].contains("5 henchmen"));
+        assert!(plan.steps[4].contains("2 gadgets"));
+    }
+
+    #[test]
+    fn goal_aims_at_a_weak_location_if_any() {
+        let plan = TemplatePlanner::new(42).plan(&all_resources());
+
+        assert!(plan.goal.contains("Las Vegas") || plan.goal.contains("Tampa"));
+    }
+
+    #[test]
+    fn only_some_weak_locations_get_their_own_step() {
+        let resources = Resources {
+            weak_locations: ["Las Vegas", "Tampa", "Gotham", "Metropolis"]
+                .into_iter()
+                .map(weak)
+                .collect(),
+            ..Resources::default()
+        };
+
+        let plan = TemplatePlanner::new(42).plan(&resources);
+
+        assert_eq!(plan.steps.len(), MAX_LOCATION_STEPS);
+    }
+
+    #[test]
+    fn plan_without_resources_starts_from_scratch() {
+        let plan = TemplatePlanner::new(42).plan(&Resources::default());
+
+        assert!(GOALS.contains(&plan.goal.as_str()));
+        assert_eq!(plan.steps, BOOTSTRAP_STEPS);
+    }
+
+    #[test]
+    fn plan_is_displayed_as_goal_and_numbered_steps() {
+        let plan = Plan {
+            goal: String::from("Steal the moon"),
+            steps: vec![
+                String::from("Build a rocket"),
+                String::from("Shrink the moon"),
+            ],
+        };
+
+        assert_eq!(
+            plan.to_string(),
+            "Steal the moon!\n1. Build a rocket\n2. Shrink the moon"
+        );
+    }
+
+    #[test]
+    fn displayed_plan_states_the_goal_once() {
+        let plan = TemplatePlanner::new(42).plan(&all_resources());
+
+        assert_eq!(plan.to_string().matches(plan.goal.as_str()).count(), 1);
+    }
+}
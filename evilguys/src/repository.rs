@@ -0,0 +1,587 @@
+//! Module for storing supervillains and retrieving them later
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, MutexGuard},
+};
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteSupervillainRepository;
+
+use crate::supervillain::{EvilError, Supervillain};
+
+/// Identifier assigned by a repository to every supervillain it stores.
+pub type SupervillainId = u64;
+
+/// Trait that represents a store of supervillains.
+///
+/// Only names and shared key are stored. The sidekick is never persisted, so supervillains are
+/// always returned without one.
+pub trait SupervillainRepository: Send + Sync {
+    /// Stores a new supervillain and returns the identifier assigned to it.
+    ///
+    /// # Errors
+    /// - `EvilError::StorageError` if the storage fails.
+    fn create(&self, supervillain: &Supervillain) -> Result<SupervillainId, EvilError>;
+
+    /// Returns the supervillain with the given identifier.
+    ///
+    /// # Errors
+    /// - `EvilError::NotFound` if there is no supervillain with that identifier.
+    /// - `EvilError::StorageError` if the storage fails.
+    fn get(&self, id: SupervillainId) -> Result<Supervillain<'static>, EvilError>;
+
+    /// Returns all the stored supervillains, sorted by identifier.
+    ///
+    /// # Errors
+    /// - `EvilError::StorageError` if the storage fails.
+    fn list(&self) -> Result<Vec<(SupervillainId, Supervillain<'static>)>, EvilError>;
+
+    /// Replaces the supervillain with the given identifier.
+    ///
+    /// # Errors
+    /// - `EvilError::NotFound` if there is no supervillain with that identifier.
+    /// - `EvilError::StorageError` if the storage fails.
+    fn update(&self, id: SupervillainId, supervillain: &Supervillain) -> Result<(), EvilError>;
+
+    /// Applies the change to the supervillain with the given identifier and stores the result,
+    /// without letting other operations run in between. Nothing is stored if the change fails.
+    ///
+    /// Returns the supervillain as stored after the change.
+    ///
+    /// # Errors
+    /// - `EvilError::NotFound` if there is no supervillain with that identifier.
+    /// - `EvilError::StorageError` if the storage fails.
+    /// - The error returned by the change, if it fails.
+    fn modify(
+        &self,
+        id: SupervillainId,
+        change: &mut dyn FnMut(&mut Supervillain<'static>) -> Result<(), EvilError>,
+    ) -> Result<Supervillain<'static>, EvilError>;
+
+    /// Removes the supervillain with the given identifier.
+    ///
+    /// # Errors
+    /// - `EvilError::NotFound` if there is no supervillain with that identifier.
+    /// - `EvilError::StorageError` if the storage fails.
+    fn delete(&self, id: SupervillainId) -> Result<(), EvilError>;
+}
+
+/// Persistent attributes of a supervillain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Record {
+    first_name: String,
+    last_name: String,
+    shared_key: String,
+}
+
+impl From<&Supervillain<'_>> for Record {
+    fn from(supervillain: &Supervillain<'_>) -> Self {
+        Record {
+            first_name: supervillain.first_name.clone(),
+            last_name: supervillain.last_name.clone(),
+            shared_key: supervillain.shared_key.clone(),
+        }
+    }
+}
+
+impl From<Record> for Supervillain<'_> {
+    fn from(record: Record) -> Self {
+        Supervillain {
+            first_name: record.first_name,
+            last_name: record.last_name,
+            shared_key: record.shared_key,
+            ..Default::default()
+        }
+    }
+}
+
+/// Repository that keeps supervillains in memory. Useful for tests and prototypes.
+///
+/// # Examples
+/// ```
+///# use evil::{Supervillain, repository::{InMemorySupervillainRepository, SupervillainRepository}};
+/// let repository = InMemorySupervillainRepository::default();
+/// let id = repository.create(&Supervillain::try_from("Lex Luthor").unwrap()).unwrap();
+/// assert_eq!(repository.get(id).unwrap().full_name(), "Lex Luthor");
+/// ```
+#[derive(Debug, Default)]
+pub struct InMemorySupervillainRepository {
+    store: Mutex<InMemoryStore>,
+}
+
+#[derive(Debug, Default)]
+struct InMemoryStore {
+    last_id: SupervillainId,
+    records: BTreeMap<SupervillainId, Record>,
+}
+
+impl InMemorySupervillainRepository {
+    fn store(&self) -> Result<MutexGuard<'_, InMemoryStore>, EvilError> {
+        self.store
+            .lock()
+            .map_err(|err| storage_error(&err.to_string()))
+    }
+}
+
+impl SupervillainRepository for InMemorySupervillainRepository {
+    fn create(&self, supervillain: &Supervillain) -> Result<SupervillainId, EvilError> {
+        let mut store = self.store()?;
+        store.last_id += 1;
+        let id = store.last_id;
+        store.records.insert(id, Record::from(supervillain));
+        Ok(id)
+    }
+
+    fn get(&self, id: SupervillainId) -> Result<Supervillain<'static>, EvilError> {
+        self.store()?
+            .records
+            .get(&id)
+            .cloned()
+            .map(Supervillain::from)
+            .ok_or_else(|| not_found(id))
+    }
+
+    fn list(&self) -> Result<Vec<(SupervillainId, Supervillain<'static>)>, EvilError> {
+        Ok(self
+            .store()?
+            .records
+            .iter()
+            .map(|(id, record)| (*id, Supervillain::from(record.clone())))
+            .collect())
+    }
+
+    fn update(&self, id: SupervillainId, supervillain: &Supervillain) -> Result<(), EvilError> {
+        let mut store = self.store()?;
+        let record = store.records.get_mut(&id).ok_or_else(|| not_found(id))?;
+        *record = Record::from(supervillain);
+        Ok(())
+    }
+
+    fn modify(
+        &self,
+        id: SupervillainId,
+        change: &mut dyn FnMut(&mut Supervillain<'static>) -> Result<(), EvilError>,
+    ) -> Result<Supervillain<'static>, EvilError> {
+        let mut store = self.store()?;
+        let record = store.records.get_mut(&id).ok_or_else(|| not_found(id))?;
+        let mut supervillain = Supervillain::from(record.clone());
+        change(&mut supervillain)?;
+        *record = Record::from(&supervillain);
+        Ok(supervillain)
+    }
+
+    fn delete(&self, id: SupervillainId) -> Result<(), EvilError> {
+        self.store()?
+            .records
+            .remove(&id)
+            .map(|_| ())
+            .ok_or_else(|| not_found(id))
+    }
+}
+
+fn not_found(id: SupervillainId) -> EvilError {
+    EvilError::NotFound {
+        entity: "supervillain".to_string(),
+        id,
+    }
+}
+
+fn storage_error(reason: &str) -> EvilError {
+    EvilError::StorageError {
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::{
+        path::Path,
+        sync::{Mutex, MutexGuard},
+    };
+
+    use rusqlite::{Connection, OptionalExtension, Row, params};
+
+    use super::{Record, SupervillainId, SupervillainRepository, not_found, storage_error};
+    use crate::supervillain::{EvilError, Supervillain};
+
+    const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS supervillains (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        first_name TEXT NOT NULL,
+        last_name TEXT NOT NULL,
+        shared_key TEXT NOT NULL
+    )";
+
+    /// Repository that keeps supervillains in a `SQLite` database.
+    ///
+    /// The table is created when the repository is opened if it doesn't exist yet.
+    pub struct SqliteSupervillainRepository {
+        connection: Mutex<Connection>,
+    }
+
+    impl SqliteSupervillainRepository {
+        /// Opens (or creates) the database stored in the given path.
+        ///
+        /// # Errors
+        /// - `EvilError::StorageError` if the database cannot be opened or initialized.
+        pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, EvilError> {
+            Self::with_connection(Connection::open(path).map_err(|err| to_storage_error(&err))?)
+        }
+
+        /// Opens a database that lives in memory and is lost when the repository is dropped.
+        ///
+        /// # Errors
+        /// - `EvilError::StorageError` if the database cannot be initialized.
+        pub fn open_in_memory() -> Result<Self, EvilError> {
+            Self::with_connection(
+                Connection::open_in_memory().map_err(|err| to_storage_error(&err))?,
+            )
+        }
+
+        fn with_connection(connection: Connection) -> Result<Self, EvilError> {
+            connection
+                .execute(CREATE_TABLE, [])
+                .map_err(|err| to_storage_error(&err))?;
+            Ok(SqliteSupervillainRepository {
+                connection: Mutex::new(connection),
+            })
+        }
+
+        fn connection(&self) -> Result<MutexGuard<'_, Connection>, EvilError> {
+            self.connection
+                .lock()
+                .map_err(|err| storage_error(&err.to_string()))
+        }
+
+        fn execute<T>(
+            &self,
+            operation: impl FnOnce(&Connection) -> rusqlite::Result<T>,
+        ) -> Result<T, EvilError> {
+            operation(&*self.connection()?).map_err(|err| to_storage_error(&err))
+        }
+    }
+
+    impl SupervillainRepository for SqliteSupervillainRepository {
+        fn create(&self, supervillain: &Supervillain) -> Result<SupervillainId, EvilError> {
+            let rowid = self.execute(|connection| {
+                connection.execute(
+                    "INSERT INTO supervillains (first_name, last_name, shared_key)
+                     VALUES (?1, ?2, ?3)",
+                    params![
+                        supervillain.first_name,
+                        supervillain.last_name,
+                        supervillain.shared_key
+                    ],
+                )?;
+                Ok(connection.last_insert_rowid())
+            })?;
+            SupervillainId::try_from(rowid).map_err(|err| storage_error(&err.to_string()))
+        }
+
+        fn get(&self, id: SupervillainId) -> Result<Supervillain<'static>, EvilError> {
+            let sql_id = to_sql_id(id)?;
+            self.execute(|connection| select_record(connection, sql_id))?
+                .map(Supervillain::from)
+                .ok_or_else(|| not_found(id))
+        }
+
+        fn list(&self) -> Result<Vec<(SupervillainId, Supervillain<'static>)>, EvilError> {
+            let rows = self.execute(|connection| {
+                let mut statement = connection.prepare(
+                    "SELECT id, first_name, last_name, shared_key FROM supervillains
+                     ORDER BY id",
+                )?;
+                statement
+                    .query_map([], |row| Ok((row.get::<_, i64>(0)?, record_from_row(row)?)))?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })?;
+            rows.into_iter()
+                .map(|(sql_id, record)| {
+                    SupervillainId::try_from(sql_id)
+                        .map(|id| (id, Supervillain::from(record)))
+                        .map_err(|err| storage_error(&err.to_string()))
+                })
+                .collect()
+        }
+
+        fn update(&self, id: SupervillainId, supervillain: &Supervillain) -> Result<(), EvilError> {
+            let sql_id = to_sql_id(id)?;
+            let changed = self.execute(|connection| {
+                update_record(connection, sql_id, &Record::from(supervillain))
+            })?;
+            if changed == 0 {
+                Err(not_found(id))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn modify(
+            &self,
+            id: SupervillainId,
+            change: &mut dyn FnMut(&mut Supervillain<'static>) -> Result<(), EvilError>,
+        ) -> Result<Supervillain<'static>, EvilError> {
+            let sql_id = to_sql_id(id)?;
+            let connection = self.connection()?;
+            let transaction = connection
+                .unchecked_transaction()
+                .map_err(|err| to_storage_error(&err))?;
+            let record = select_record(&transaction, sql_id)
+                .map_err(|err| to_storage_error(&err))?
+                .ok_or_else(|| not_found(id))?;
+            let mut supervillain = Supervillain::from(record);
+            change(&mut supervillain)?;
+            update_record(&transaction, sql_id, &Record::from(&supervillain))
+                .and_then(|_| transaction.commit())
+                .map_err(|err| to_storage_error(&err))?;
+            Ok(supervillain)
+        }
+
+        fn delete(&self, id: SupervillainId) -> Result<(), EvilError> {
+            let sql_id = to_sql_id(id)?;
+            let changed = self.execute(|connection| {
+                connection.execute("DELETE FROM supervillains WHERE id = ?1", [sql_id])
+            })?;
+            if changed == 0 {
+                Err(not_found(id))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    fn select_record(connection: &Connection, sql_id: i64) -> rusqlite::Result<Option<Record>> {
+        connection
+            .query_row(
+                "SELECT first_name, last_name, shared_key FROM supervillains WHERE id = ?1",
+                [sql_id],
+                record_from_row,
+            )
+            .optional()
+    }
+
+    fn update_record(
+        connection: &Connection,
+        sql_id: i64,
+        record: &Record,
+    ) -> rusqlite::Result<usize> {
+        connection.execute(
+            "UPDATE supervillains SET first_name = ?1, last_name = ?2, shared_key = ?3
+             WHERE id = ?4",
+            params![
+                record.first_name,
+                record.last_name,
+                record.shared_key,
+                sql_id
+            ],
+        )
+    }
+
+    fn record_from_row(row: &Row<'_>) -> rusqlite::Result<Record> {
+        Ok(Record {
+            first_name: row.get("first_name")?,
+            last_name: row.get("last_name")?,
+            shared_key: row.get("shared_key")?,
+        })
+    }
+
+    fn to_sql_id(id: SupervillainId) -> Result<i64, EvilError> {
+        i64::try_from(id).map_err(|_| not_found(id))
+    }
+
+    fn to_storage_error(err: &rusqlite::Error) -> EvilError {
+        storage_error(&err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertables::{assert_matches, assert_ok, assert_ok_eq_x};
+
+    use crate::test_common;
+
+    use super::*;
+
+    fn primary() -> Supervillain<'static> {
+        Supervillain {
+            first_name: test_common::PRIMARY_FIRST_NAME.to_string(),
+            last_name: test_common::PRIMARY_LAST_NAME.to_string(),
+            shared_key: String::from("Kryptonite"),
+            ..Default::default()
+        }
+    }
+
+    fn secondary() -> Supervillain<'static> {
+        Supervillain {
+            first_name: test_common::SECONDARY_FIRST_NAME.to_string(),
+            last_name: test_common::SECONDARY_LAST_NAME.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn created_supervillain_can_be_retrieved(sut: &impl SupervillainRepository) {
+        let id = sut.create(&primary()).unwrap();
+
+        let stored = sut.get(id).unwrap();
+
+        assert_eq!(stored.full_name(), test_common::PRIMARY_FULL_NAME);
+        assert_eq!(stored.shared_key, "Kryptonite");
+    }
+
+    fn list_returns_supervillains_sorted_by_id(sut: &impl SupervillainRepository) {
+        let first_id = sut.create(&primary()).unwrap();
+        let second_id = sut.create(&secondary()).unwrap();
+
+        let stored = sut.list().unwrap();
+
+        let names = stored
+            .iter()
+            .map(|(id, supervillain)| (*id, supervillain.full_name()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                (first_id, test_common::PRIMARY_FULL_NAME.to_string()),
+                (second_id, test_common::SECONDARY_FULL_NAME.to_string())
+            ]
+        );
+    }
+
+    fn update_replaces_stored_supervillain(sut: &impl SupervillainRepository) {
+        let id = sut.create(&primary()).unwrap();
+
+        assert_ok!(sut.update(id, &secondary()));
+
+        assert_ok_eq_x!(
+            sut.get(id)
+                .map(|supervillain| supervillain.full_name())
+                .as_deref(),
+            test_common::SECONDARY_FULL_NAME
+        );
+    }
+
+    fn modify_stores_changed_supervillain(sut: &impl SupervillainRepository) {
+        let id = sut.create(&primary()).unwrap();
+
+        let modified = sut.modify(id, &mut |supervillain| {
+            supervillain.shared_key = String::from("Lead");
+            Ok(())
+        });
+
+        assert_ok_eq_x!(
+            modified
+                .map(|supervillain| supervillain.shared_key)
+                .as_deref(),
+            "Lead"
+        );
+        assert_eq!(sut.get(id).unwrap().shared_key, "Lead");
+    }
+
+    fn failed_modify_keeps_stored_supervillain(sut: &impl SupervillainRepository) {
+        let id = sut.create(&primary()).unwrap();
+
+        let modified = sut.modify(id, &mut |supervillain| {
+            supervillain.shared_key = String::from("Lead");
+            supervillain.set_full_name("")
+        });
+
+        assert_matches!(modified, Err(EvilError::ParseError { .. }));
+        assert_eq!(sut.get(id).unwrap().shared_key, "Kryptonite");
+    }
+
+    fn deleted_supervillain_is_not_found(sut: &impl SupervillainRepository) {
+        let id = sut.create(&primary()).unwrap();
+
+        assert_ok!(sut.delete(id));
+
+        assert_matches!(sut.get(id), Err(EvilError::NotFound { id: not_found_id, .. }) if not_found_id == id);
+    }
+
+    fn operations_on_unknown_id_are_not_found(sut: &impl SupervillainRepository) {
+        assert_matches!(sut.get(42), Err(EvilError::NotFound { .. }));
+        assert_matches!(sut.update(42, &primary()), Err(EvilError::NotFound { .. }));
+        assert_matches!(
+            sut.modify(42, &mut |_| Ok(())),
+            Err(EvilError::NotFound { .. })
+        );
+        assert_matches!(sut.delete(42), Err(EvilError::NotFound { .. }));
+    }
+
+    #[test]
+    fn in_memory_created_supervillain_can_be_retrieved() {
+        created_supervillain_can_be_retrieved(&InMemorySupervillainRepository::default());
+    }
+
+    #[test]
+    fn in_memory_list_returns_supervillains_sorted_by_id() {
+        list_returns_supervillains_sorted_by_id(&InMemorySupervillainRepository::default());
+    }
+
+    #[test]
+    fn in_memory_update_replaces_stored_supervillain() {
+        update_replaces_stored_supervillain(&InMemorySupervillainRepository::default());
+    }
+
+    #[test]
+    fn in_memory_modify_stores_changed_supervillain() {
+        modify_stores_changed_supervillain(&InMemorySupervillainRepository::default());
+    }
+
+    #[test]
+    fn in_memory_failed_modify_keeps_stored_supervillain() {
+        failed_modify_keeps_stored_supervillain(&InMemorySupervillainRepository::default());
+    }
+
+    #[test]
+    fn in_memory_deleted_supervillain_is_not_found() {
+        deleted_supervillain_is_not_found(&InMemorySupervillainRepository::default());
+    }
+
+    #[test]
+    fn in_memory_operations_on_unknown_id_are_not_found() {
+        operations_on_unknown_id_are_not_found(&InMemorySupervillainRepository::default());
+    }
+
+    #[cfg(feature = "sqlite")]
+    mod sqlite {
+        use super::*;
+
+        fn sut() -> SqliteSupervillainRepository {
+            SqliteSupervillainRepository::open_in_memory().unwrap()
+        }
+
+        #[test]
+        fn sqlite_created_supervillain_can_be_retrieved() {
+            created_supervillain_can_be_retrieved(&sut());
+        }
+
+        #[test]
+        fn sqlite_list_returns_supervillains_sorted_by_id() {
+            list_returns_supervillains_sorted_by_id(&sut());
+        }
+
+        #[test]
+        fn sqlite_update_replaces_stored_supervillain() {
+            update_replaces_stored_supervillain(&sut());
+        }
+
+        #[test]
+        fn sqlite_modify_stores_changed_supervillain() {
+            modify_stores_changed_supervillain(&sut());
+        }
+
+        #[test]
+        fn sqlite_failed_modify_keeps_stored_supervillain() {
+            failed_modify_keeps_stored_supervillain(&sut());
+        }
+
+        #[test]
+        fn sqlite_deleted_supervillain_is_not_found() {
+            deleted_supervillain_is_not_found(&sut());
+        }
+
+        #[test]
+        fn sqlite_operations_on_unknown_id_are_not_found() {
+            operations_on_unknown_id_are_not_found(&sut());
+        }
+    }
+}
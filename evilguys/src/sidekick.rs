@@ -1,20 +1,34 @@
 //! Module for sideckicks and all the related functionality
 #![allow(dead_code)]
 
+use std::sync::Mutex;
+
 #[cfg(test)]
 use mockall::mock;
 
-use crate::Gadget;
+use crate::{Decipher, Gadget, gadget::AsyncGadget, location::Location, supervillain::EvilError};
 
 /// Type that represents a sidekick.
 pub struct Sidekick<'a> {
     gadget: Box<dyn Gadget + 'a>,
+    decipher: Box<dyn Decipher + Send + 'a>,
+    shared_key: String,
+    plans: Mutex<Vec<String>>,
 }
 
 impl<'a> Sidekick<'a> {
-    fn new<G: Gadget + 'a>(gadget: G) -> Self {
+    /// Creates a sidekick that reads the plans it is told with the given decipher and the key
+    /// shared with its supervillain.
+    pub fn new<G: Gadget + 'a, D: Decipher + Send + 'a>(
+        gadget: G,
+        decipher: D,
+        shared_key: &str,
+    ) -> Self {
         Sidekick {
             gadget: Box::new(gadget),
+            decipher: Box::new(decipher),
+            shared_key: shared_key.to_string(),
+            plans: Mutex::new(vec![]),
         }
     }
 
@@ -23,11 +37,37 @@ impl<'a> Sidekick<'a> {
         true
     }
 
-    pub const fn get_weak_targets<G: Gadget>(&self, _gadget: &G) -> Vec<String> {
+    pub const fn get_weak_targets<G: Gadget>(&self, _gadget: &G) -> Vec<Location> {
+        vec![]
+    }
+
+    /// Same as [`Self::get_weak_targets`], but waiting for the gadget to do its stuff first.
+    pub async fn get_weak_targets_async(&self, gadget: &dyn AsyncGadget) -> Vec<Location> {
+        gadget.do_stuff().await;
         vec![]
     }
 
-    pub const fn tell(&self, _ciphered_msg: &str) {}
+    /// Deciphers the message with the shared key and remembers the resulting plan.
+    ///
+    /// # Errors
+    /// - `EvilError::CipherError` if the message cannot be deciphered. The plan is not remembered.
+    ///
+    /// # Panics
+    /// - If a previous holder of the plans panicked.
+    pub fn tell(&self, ciphered_msg: &str) -> Result<(), EvilError> {
+        let plan = self.decipher.restore(ciphered_msg, &self.shared_key)?;
+        self.plans.lock().expect("Plans poisoned").push(plan);
+        Ok(())
+    }
+
+    /// Returns the plans told to this sidekick, in the order they were received.
+    ///
+    /// # Panics
+    /// - If a previous holder of the plans panicked.
+    #[must_use]
+    pub fn plans(&self) -> Vec<String> {
+        self.plans.lock().expect("Plans poisoned").clone()
+    }
 }
 
 #[cfg(test)]
@@ -35,7 +75,68 @@ mock! {
     #[derive(Debug)]
     pub Sidekick<'a> {
         pub fn agree(&self) -> bool;
-        pub fn get_weak_targets(&self, gadget: &'a dyn Gadget) -> Vec<String>;
-        pub fn tell(&self, ciphered_msg: &str);
+        pub fn get_weak_targets(&self, gadget: &'a dyn Gadget) -> Vec<Location>;
+        pub async fn get_weak_targets_async(&self, gadget: &'a dyn AsyncGadget) -> Vec<Location>;
+        pub fn tell(&self, ciphered_msg: &str) -> Result<(), EvilError>;
+        pub fn plans(&self) -> Vec<String>;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertables::assert_err;
+
+    use crate::{
+        Cipher,
+        cipher::{Authenticated, Vigenere},
+        gadget::{MockAsyncGadget, MockGadget},
+        test_common,
+    };
+
+    use super::*;
+
+    const SHARED_KEY: &str = "Kryptonite";
+
+    #[test]
+    fn tell_deciphers_message_with_shared_key() -> Result<(), EvilError> {
+        let sut = Sidekick::new(MockGadget::new(), Vigenere, SHARED_KEY);
+        let ciphered_msg = Vigenere.transform(test_common::MAIN_SECRET_MESSAGE, SHARED_KEY)?;
+
+        sut.tell(&ciphered_msg)?;
+
+        assert_eq!(sut.plans(), vec![test_common::MAIN_SECRET_MESSAGE]);
+        Ok(())
+    }
+
+    #[test]
+    fn plans_are_kept_in_order() -> Result<(), EvilError> {
+        let sut = Sidekick::new(MockGadget::new(), Vigenere, SHARED_KEY);
+
+        sut.tell(&Vigenere.transform("First", SHARED_KEY)?)?;
+        sut.tell(&Vigenere.transform("Second", SHARED_KEY)?)?;
+
+        assert_eq!(sut.plans(), vec!["First", "Second"]);
+        Ok(())
+    }
+
+    #[test]
+    fn tell_with_message_ciphered_with_other_key_fails_and_forgets_it() -> Result<(), EvilError> {
+        let sut = Sidekick::new(MockGadget::new(), Authenticated, SHARED_KEY);
+        let ciphered_msg = Authenticated.transform(test_common::MAIN_SECRET_MESSAGE, "Other")?;
+
+        assert_err!(sut.tell(&ciphered_msg));
+        assert!(sut.plans().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn weak_targets_async_waits_for_gadget() {
+        let sut = Sidekick::new(MockGadget::new(), Vigenere, SHARED_KEY);
+        let mut gadget = MockAsyncGadget::new();
+        gadget.expect_do_stuff().once().return_const(());
+
+        let targets = sut.get_weak_targets_async(&gadget).await;
+
+        assert!(targets.is_empty());
     }
 }
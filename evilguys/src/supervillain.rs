@@ -3,10 +3,11 @@
 #[cfg(not(test))]
 use std::fs::File;
 use std::{
-    cell::RefCell,
+    fmt::{self, Display},
     io::{self, BufRead, Read, Write},
-    path::Path,
-    rc::Rc,
+    panic,
+    path::{Path, PathBuf},
+    sync::Arc,
     time::Duration,
 };
 #[cfg(test)]
@@ -18,24 +19,55 @@ use mockall::automock;
 use mockall_double::double;
 use rand::Rng;
 use thiserror::Error;
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    task::JoinSet,
+};
 
 #[cfg_attr(test, double)]
 use crate::sidekick::Sidekick;
-use crate::{Cipher, Gadget, Henchman};
+use crate::{
+    Cipher, Gadget, Henchman,
+    campaign::StageOutcome,
+    cipher::CipherErrorKind,
+    crew::{Crew, CrewError, CrewReport},
+    gadget::AsyncGadget,
+    henchman::{AsyncHenchman, HenchmanError, Outcome, Task},
+    location::{Location, parse_listing},
+    name::FullName,
+    orders::{Order, OrdersFormat, WriteOptions, parse_orders},
+    plan::{Plan, Planner, Resources},
+    target::{FirstTarget, TargetSelector},
+};
 #[cfg(not(test))]
-use aux::{open_buf_read, open_write_execute};
+use aux::{open_buf_read, open_write_execute, open_write_execute_async, read_all_async};
 #[cfg(test)]
-use tests::doubles::{open_buf_read, open_write_execute};
+use tests::doubles::{open_buf_read, open_write_execute, open_write_execute_async, read_all_async};
 
-const LISTING_PATH: &str = "tmp/listings.csv";
+/// Path of the listing of locations used when the supervillain doesn't have one configured.
+pub const LISTING_PATH: &str = "tmp/listings.csv";
 
 /// Type that represents supervillains.
+///
+/// With the `serde` feature enabled it can be serialized and deserialized, but the sidekick, the
+/// listing path and the target selector are always skipped. The shared key is a secret, so it
+/// can be deserialized, but it is never serialized.
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Supervillain<'a> {
     pub first_name: String,
     pub last_name: String,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub sidekick: Option<Sidekick<'a>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing, default))]
     pub shared_key: String,
+    /// Listing of locations to scan. [`LISTING_PATH`] is used when it is `None`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub listing_path: Option<PathBuf>,
+    /// Strategy used to choose where to build the headquarters. [`FirstTarget`] is used when
+    /// it is `None`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub target_selector: Option<Box<dyn TargetSelector + Send + Sync>>,
 }
 
 #[cfg_attr(test, automock)]
@@ -65,14 +97,26 @@ impl Supervillain<'_> {
 
     /// Sets `first_name` and `last_name` from the name provided.
     ///
-    /// # Panics
-    /// - If number doesn't have two components (words)
-    pub fn set_full_name(&mut self, name: &str) {
-        let components = name.split(' ').collect::<Vec<_>>();
-        println!("Received {} components.", components.len());
-        assert!(components.len() == 2, "Name must have first and last name");
-        self.first_name = components[0].to_string();
-        self.last_name = components[1].to_string();
+    /// The name is parsed as a [`FullName`]: title and given names become the first name, and
+    /// the last name keeps its particles.
+    ///
+    /// # Examples
+    /// ```
+    ///# use evil::supervillain::Supervillain;
+    /// let mut doom = Supervillain::default();
+    /// doom.set_full_name("Doctor Victor von Doom").unwrap();
+    /// assert_eq!(doom.first_name, "Doctor Victor");
+    /// assert_eq!(doom.last_name, "von Doom");
+    /// ```
+    ///
+    /// # Errors
+    /// - `EvilError::ParseError` if the name doesn't have at least two words or contains invalid
+    ///   characters. Names are left untouched.
+    pub fn set_full_name(&mut self, name: &str) -> Result<(), EvilError> {
+        let name = name.parse::<FullName>()?;
+        self.first_name = name.first_name();
+        self.last_name = name.last_name;
+        Ok(())
     }
 
     pub fn attack(&self, weapon: &impl Megaweapon, intense: bool) {
@@ -86,9 +130,25 @@ impl Supervillain<'_> {
         }
     }
 
-    pub async fn come_up_with_plan(&self) -> String {
-        tokio::time::sleep(Duration::from_millis(100)).await;
-        String::from("Take over the world!")
+    /// Comes up with a plan using the planner and the resources provided.
+    ///
+    /// The resources are completed with what the supervillain knows: whether there is a
+    /// sidekick and, if none were provided, the weak locations of the listing. A listing that
+    /// cannot be read just leaves the plan without locations.
+    pub async fn come_up_with_plan<P: Planner>(
+        &self,
+        planner: &mut P,
+        mut resources: Resources,
+    ) -> Plan {
+        resources.sidekick |= self.sidekick.is_some();
+        if resources.weak_locations.is_empty()
+            && let Ok(listing) = read_all_async(self.listing_path()).await
+        {
+            resources.weak_locations = self
+                .weak_locations_from(listing.as_slice())
+                .unwrap_or_default();
+        }
+        planner.plan(&resources)
     }
 
     pub fn conspire(&mut self) {
@@ -99,88 +159,621 @@ impl Supervillain<'_> {
         }
     }
 
+    /// Returns the strategy used to choose where to build the headquarters.
+    #[must_use]
+    pub fn target_selector(&self) -> &dyn TargetSelector {
+        match self.target_selector {
+            Some(ref selector) => selector.as_ref(),
+            None => &FirstTarget,
+        }
+    }
+
+    /// Asks the sidekick for weak targets and has the henchman build the secret headquarters
+    /// in the one chosen by the target selector of the supervillain.
+    ///
+    /// Returns what happened, including the reason why nothing was built.
+    #[must_use]
     pub fn start_world_domination_stage1<H: Henchman, G: Gadget>(
         &self,
         henchman: &mut H,
         gadget: &G,
-    ) {
-        if let Some(ref sidekick) = self.sidekick {
-            let targets = sidekick.get_weak_targets(gadget);
-            if !targets.is_empty() {
-                henchman.build_secret_hq(targets[0].clone());
-            }
+    ) -> StageOutcome {
+        self.start_world_domination_stage1_with(henchman, gadget, self.target_selector())
+    }
+
+    /// Same as [`Self::start_world_domination_stage1`], but choosing the target with the
+    /// provided selector.
+    #[must_use]
+    pub fn start_world_domination_stage1_with<H, G, S>(
+        &self,
+        henchman: &mut H,
+        gadget: &G,
+        selector: &S,
+    ) -> StageOutcome
+    where
+        H: Henchman,
+        G: Gadget,
+        S: TargetSelector + ?Sized,
+    {
+        match self.find_weak_targets(gadget) {
+            Ok(targets) => self.build_headquarters(henchman, &targets, selector),
+            Err(outcome) => outcome,
         }
     }
 
-    pub fn start_world_domination_stage2<H: Henchman>(&self, henchman: &H) {
-        henchman.fight_enemies();
-        henchman.do_hard_things();
+    /// Asks the sidekick for the weak targets, which is the first part of stage 1.
+    ///
+    /// # Errors
+    /// - `StageOutcome::NoSidekick` or `StageOutcome::NoTargets` if stage 1 can't go on.
+    pub fn find_weak_targets<G: Gadget>(&self, gadget: &G) -> Result<Vec<Location>, StageOutcome> {
+        weak_targets_found(self.stage1_sidekick()?.get_weak_targets(gadget))
     }
 
-    pub fn tell_plans<C: Cipher>(&self, secret: &str, cipher: &C) {
-        if let Some(ref sidekick) = self.sidekick {
-            let ciphered_msg = cipher.transform(secret, &self.shared_key);
-            sidekick.tell(&ciphered_msg);
+    /// Same as [`Self::find_weak_targets`], but with an asynchronous gadget.
+    ///
+    /// # Errors
+    /// - `StageOutcome::NoSidekick` or `StageOutcome::NoTargets` if stage 1 can't go on.
+    pub async fn find_weak_targets_async(
+        &self,
+        gadget: &dyn AsyncGadget,
+    ) -> Result<Vec<Location>, StageOutcome> {
+        weak_targets_found(self.stage1_sidekick()?.get_weak_targets_async(gadget).await)
+    }
+
+    /// Has the henchman build the secret headquarters in the target chosen by the selector,
+    /// which is the last part of stage 1.
+    #[must_use]
+    pub fn build_headquarters<H, S>(
+        &self,
+        henchman: &mut H,
+        targets: &[Location],
+        selector: &S,
+    ) -> StageOutcome
+    where
+        H: Henchman,
+        S: TargetSelector + ?Sized,
+    {
+        match choose_target(targets, selector) {
+            Ok(target) => {
+                headquarters_outcome(henchman.build_secret_hq(target.name.clone()), target)
+            }
+            Err(outcome) => outcome,
         }
     }
 
+    /// Same as [`Self::start_world_domination_stage1`], but the headquarters are built by the
+    /// members of the crew that its policy assigns. They are built if any of them succeeds.
     #[must_use]
-    pub fn are_there_vulnerable_locations(&self) -> Option<bool> {
-        let mut listing = String::new();
-        let Ok(mut file_listing) = File::open(LISTING_PATH) else {
-            return None;
+    pub fn start_world_domination_stage1_with_crew<G: Gadget>(
+        &self,
+        crew: &mut Crew<'_>,
+        gadget: &G,
+    ) -> StageOutcome {
+        let target = match self
+            .find_weak_targets(gadget)
+            .and_then(|targets| choose_target(&targets, self.target_selector()))
+        {
+            Ok(target) => target,
+            Err(outcome) => return outcome,
+        };
+        let report = crew.perform(Task::BuildSecretHq {
+            location: target.name.clone(),
+        });
+        headquarters_outcome(report.into_result(), target)
+    }
+
+    /// Same as [`Self::start_world_domination_stage1`], but with an asynchronous henchman and
+    /// gadget. The henchman refuses if the headquarters aren't built within the timeout.
+    #[must_use]
+    pub async fn start_world_domination_stage1_async(
+        &self,
+        henchman: &dyn AsyncHenchman,
+        gadget: &dyn AsyncGadget,
+        timeout: Duration,
+    ) -> StageOutcome {
+        let target = match self
+            .find_weak_targets_async(gadget)
+            .await
+            .and_then(|targets| choose_target(&targets, self.target_selector()))
+        {
+            Ok(target) => target,
+            Err(outcome) => return outcome,
         };
-        let Ok(n) = file_listing.read_to_string(&mut listing) else {
-            return None;
+        let task = Task::BuildSecretHq {
+            location: target.name.clone(),
         };
+        let result =
+            with_timeout(timeout, task, henchman.build_secret_hq(target.name.clone())).await;
+        headquarters_outcome(result, target)
+    }
 
-        for line in listing.lines() {
-            if line.ends_with("weak") {
-                return Some(true);
-            }
+    /// Has the henchman fight the enemies and then do the hard things, stopping at the first
+    /// task that fails.
+    ///
+    /// # Errors
+    /// - `HenchmanError` of the first task that failed. The tasks after it aren't attempted.
+    pub fn start_world_domination_stage2<H: Henchman>(
+        &self,
+        henchman: &H,
+    ) -> Result<Vec<Outcome>, HenchmanError> {
+        self.start_world_domination_stage2_with_retries(henchman, 0)
+    }
+
+    /// Same as [`Self::start_world_domination_stage2`], but trying each task up to `retries`
+    /// more times before giving up.
+    ///
+    /// # Errors
+    /// - `HenchmanError` of the last attempt of the first task that couldn't be performed.
+    pub fn start_world_domination_stage2_with_retries<H: Henchman>(
+        &self,
+        henchman: &H,
+        retries: usize,
+    ) -> Result<Vec<Outcome>, HenchmanError> {
+        Ok(vec![
+            self.fight_enemies_with_retries(henchman, retries)?,
+            self.do_hard_things_with_retries(henchman, retries)?,
+        ])
+    }
+
+    /// Has the henchman fight the enemies, trying up to `retries` more times before giving up.
+    ///
+    /// # Errors
+    /// - `HenchmanError` of the last attempt.
+    pub fn fight_enemies_with_retries<H: Henchman>(
+        &self,
+        henchman: &H,
+        retries: usize,
+    ) -> Result<Outcome, HenchmanError> {
+        with_retries(retries, || henchman.fight_enemies())
+    }
+
+    /// Has the henchman do the hard things, trying up to `retries` more times before giving up.
+    ///
+    /// # Errors
+    /// - `HenchmanError` of the last attempt.
+    pub fn do_hard_things_with_retries<H: Henchman>(
+        &self,
+        henchman: &H,
+        retries: usize,
+    ) -> Result<Outcome, HenchmanError> {
+        with_retries(retries, || henchman.do_hard_things())
+    }
+
+    /// Same as [`Self::start_world_domination_stage2`], but with asynchronous henchmen that
+    /// work concurrently in their own tokio tasks. Each task fails if it isn't done within the
+    /// timeout.
+    ///
+    /// Returns the outcomes of every henchman, in the same order as the henchmen. Each one stops
+    /// at its first task that fails, without affecting the others. Henchmen whose tokio task is
+    /// cancelled fail with `HenchmanError::Cancelled`.
+    ///
+    /// # Panics
+    /// - If any of the henchmen panics. The tasks of the other henchmen are aborted first.
+    pub async fn start_world_domination_stage2_async(
+        &self,
+        henchmen: &[Arc<dyn AsyncHenchman>],
+        timeout: Duration,
+    ) -> Vec<Result<Vec<Outcome>, HenchmanError>> {
+        let mut tasks = JoinSet::new();
+        for (index, member) in henchmen.iter().enumerate() {
+            let member = Arc::clone(member);
+            tasks.spawn(async move {
+                let result = async {
+                    Ok(vec![
+                        with_timeout(timeout, Task::FightEnemies, member.fight_enemies()).await?,
+                        with_timeout(timeout, Task::DoHardThings, member.do_hard_things()).await?,
+                    ])
+                }
+                .await;
+                (index, result)
+            });
+        }
+        joined_in_order(tasks, henchmen.len()).await
+    }
+
+    /// Same as [`Self::start_world_domination_stage2`], but the tasks are performed by the
+    /// members of the crew that its policy assigns. A task fails only if none of them succeeds.
+    ///
+    /// # Errors
+    /// - `CrewError::Henchman` of the last member assigned to the first task that failed.
+    /// - `CrewError::Empty` if the crew has no members.
+    pub fn start_world_domination_stage2_with_crew(
+        &self,
+        crew: &mut Crew<'_>,
+    ) -> Result<Vec<CrewReport>, CrewError> {
+        Ok(vec![
+            crew.perform(Task::FightEnemies).into_result()?,
+            crew.perform(Task::DoHardThings).into_result()?,
+        ])
+    }
+
+    /// Ciphers the secret with the shared key and tells it to the sidekick, if any.
+    ///
+    /// # Errors
+    /// - `EvilError::CipherError` if the secret cannot be ciphered with the shared key or the
+    ///   sidekick cannot decipher it.
+    pub fn tell_plans<C: Cipher>(&self, secret: &str, cipher: &C) -> Result<(), EvilError> {
+        if let Some(ref sidekick) = self.sidekick {
+            let ciphered_msg = cipher.transform(secret, &self.shared_key)?;
+            sidekick.tell(&ciphered_msg)?;
         }
-        Some(false)
+        Ok(())
     }
 
+    /// Returns the path of the listing of locations used by this supervillain.
     #[must_use]
-    pub fn are_there_vulnerable_locations_efficient(&self) -> Option<bool> {
-        let buf_listing = open_buf_read(LISTING_PATH)?;
-        let mut list_iter = buf_listing.lines();
-        for line in list_iter {
-            if let Ok(line) = line
-                && line.ends_with("weak")
-            {
-                return Some(true);
+    pub fn listing_path(&self) -> &Path {
+        self.listing_path
+            .as_deref()
+            .unwrap_or_else(|| Path::new(LISTING_PATH))
+    }
+
+    /// Checks whether the listing contains any weak location, reading it all at once.
+    ///
+    /// # Errors
+    /// - `EvilError::Io` if the listing cannot be opened or read.
+    /// - `EvilError::ListingFormat` if a line before the first weak location is malformed.
+    pub fn are_there_vulnerable_locations(&self) -> Result<bool, EvilError> {
+        let path = self.listing_path();
+        let mut listing = String::new();
+        let mut file_listing = File::open(path).map_err(|err| io_error(path, &err))?;
+        file_listing
+            .read_to_string(&mut listing)
+            .map_err(|err| io_error(path, &err))?;
+
+        contains_weak_location(listing.as_bytes())
+    }
+
+    /// Checks whether the listing contains any weak location, reading it line by line and
+    /// stopping at the first weak one.
+    ///
+    /// # Errors
+    /// - `EvilError::Io` if the listing cannot be opened or read.
+    /// - `EvilError::ListingFormat` if a line before the first weak location is malformed or
+    ///   cannot be decoded.
+    pub fn are_there_vulnerable_locations_efficient(&self) -> Result<bool, EvilError> {
+        let path = self.listing_path();
+        let buf_listing = open_buf_read(path).map_err(|err| io_error(path, &err))?;
+        contains_weak_location(buf_listing)
+    }
+
+    /// Checks whether the listing contains any weak location without blocking the runtime.
+    ///
+    /// The future doesn't borrow the supervillain, so it can be sent to other threads.
+    ///
+    /// # Errors
+    /// - `EvilError::Io` if the listing cannot be opened or read.
+    /// - `EvilError::ListingFormat` if a line before the first weak location is malformed or
+    ///   cannot be decoded.
+    pub fn are_there_vulnerable_locations_async(
+        &self,
+    ) -> impl Future<Output = Result<bool, EvilError>> + Send + use<> {
+        let path = self.listing_path().to_path_buf();
+        async move {
+            let listing = read_all_async(&path)
+                .await
+                .map_err(|err| io_error(&path, &err))?;
+            contains_weak_location(listing.as_slice())
+        }
+    }
+
+    /// Returns the weak locations of the listing, in the order they appear.
+    ///
+    /// Lines that cannot be parsed are skipped.
+    ///
+    /// # Errors
+    /// - `EvilError::Io` if the listing cannot be opened or read.
+    pub fn weak_locations(&self) -> Result<Vec<Location>, EvilError> {
+        let path = self.listing_path();
+        let buf_listing = open_buf_read(path).map_err(|err| io_error(path, &err))?;
+        self.weak_locations_from(buf_listing)
+    }
+
+    /// Returns the weak locations of a listing read from any source, in the order they appear.
+    ///
+    /// Lines that cannot be parsed are skipped.
+    ///
+    /// # Errors
+    /// - `EvilError::Io` if the listing cannot be read. Nothing else is read after the error.
+    ///
+    /// # Examples
+    /// ```
+    ///# use evil::supervillain::Supervillain;
+    /// let lex = Supervillain::default();
+    /// let listing = "Madrid,strong\nLas Vegas,weak\n";
+    /// let weak = lex.weak_locations_from(listing.as_bytes()).unwrap();
+    /// assert_eq!(weak[0].name, "Las Vegas");
+    /// ```
+    pub fn weak_locations_from<R: BufRead>(&self, listing: R) -> Result<Vec<Location>, EvilError> {
+        let mut weak = vec![];
+        for location in parse_listing(listing) {
+            match location {
+                Ok(location) if location.is_weak() => weak.push(location),
+                Ok(_) | Err(EvilError::ListingFormat { .. }) => {}
+                Err(err) => return Err(err),
             }
         }
-        Some(false)
+        Ok(weak)
+    }
+
+    /// Writes orders to a file in the given path, in plain text.
+    ///
+    /// Orders can be anything that converts into an [`Order`], including plain strings.
+    ///
+    /// # Errors
+    /// - `io::Error` if file cannot be opened or written.
+    pub fn spread_orders_by_file<P, I>(&self, path: P, orders: I) -> Result<usize, io::Error>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator,
+        I::Item: Into<Order>,
+    {
+        self.spread_orders_by_file_as(path, orders, OrdersFormat::default())
     }
 
-    /// Writes orders to a file in the given path.
+    /// Writes orders to a file in the given path, using the provided format.
     ///
     /// # Errors
     /// - `io::Error` if file cannot be opened or written.
-    pub fn spread_orders_by_file<P: AsRef<Path>>(
+    pub fn spread_orders_by_file_as<P, I>(
+        &self,
+        path: P,
+        orders: I,
+        format: OrdersFormat,
+    ) -> Result<usize, io::Error>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator,
+        I::Item: Into<Order>,
+    {
+        let options = WriteOptions {
+            format,
+            ..WriteOptions::default()
+        };
+        self.spread_orders_by_file_with(path, orders, options)
+    }
+
+    /// Writes orders to a file in the given path, using the provided format, write mode and
+    /// directory creation options.
+    ///
+    /// # Errors
+    /// - `io::Error` if file cannot be opened or written. When overwriting, the previous file is
+    ///   left untouched.
+    pub fn spread_orders_by_file_with<P, I>(
         &self,
         path: P,
-        orders: Vec<String>,
-    ) -> Result<usize, io::Error> {
-        open_write_execute(path, |mut buf_orders: Rc<RefCell<dyn Write>>| {
-            let mut buf_orders = buf_orders.borrow_mut();
-            let mut orders_written = 0;
-            writeln!(
+        orders: I,
+        options: WriteOptions,
+    ) -> Result<usize, io::Error>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator,
+        I::Item: Into<Order>,
+    {
+        open_write_execute(path, options, |buf_orders: &mut dyn Write, has_content| {
+            write_or_append_orders(
                 buf_orders,
-                "I, {}, as your leader, tell you to:",
-                &self.full_name()
-            )?;
+                has_content,
+                options.format,
+                &self.full_name(),
+                orders,
+            )
+        })
+    }
+
+    /// Writes orders to a file in the given path without blocking the runtime, using the
+    /// provided format, write mode and directory creation options.
+    ///
+    /// The orders are collected before being written, so the future doesn't borrow the
+    /// supervillain and can be sent to other threads.
+    ///
+    /// # Errors
+    /// - `io::Error` if file cannot be opened or written. When overwriting, the previous file is
+    ///   left untouched.
+    pub fn spread_orders_by_file_async<P, I>(
+        &self,
+        path: P,
+        orders: I,
+        options: WriteOptions,
+    ) -> impl Future<Output = Result<usize, io::Error>> + Send + use<P, I>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator,
+        I::Item: Into<Order>,
+    {
+        let path = path.as_ref().to_path_buf();
+        let leader = self.full_name();
+        let orders = orders.into_iter().map(Into::into).collect::<Vec<Order>>();
+        open_write_execute_async(path, options, move |out, has_content| {
+            write_or_append_orders(out, has_content, options.format, &leader, orders)
+        })
+    }
+
+    /// Writes orders to any writer, like a socket or the body of a response, using the provided
+    /// format.
+    ///
+    /// # Errors
+    /// - `io::Error` if the orders cannot be written.
+    ///
+    /// # Examples
+    /// ```
+    ///# use evil::{orders::OrdersFormat, supervillain::Supervillain};
+    /// let lex = Supervillain::try_from("Lex Luthor").unwrap();
+    /// let mut output = vec![];
+    /// lex.spread_orders_to(&mut output, ["Fight enemies"], OrdersFormat::Markdown)
+    ///     .unwrap();
+    /// assert!(output.ends_with(b"1. Fight enemies\n"));
+    /// ```
+    pub fn spread_orders_to<W, I>(
+        &self,
+        out: &mut W,
+        orders: I,
+        format: OrdersFormat,
+    ) -> Result<usize, io::Error>
+    where
+        W: Write + ?Sized,
+        I: IntoIterator,
+        I::Item: Into<Order>,
+    {
+        format.write_orders(out, &self.full_name(), orders)
+    }
+
+    /// Writes orders to any asynchronous writer using the provided format.
+    ///
+    /// The orders are formatted in memory before being written, so the future doesn't borrow
+    /// the supervillain and can be sent to other threads.
+    ///
+    /// # Errors
+    /// - `io::Error` if the orders cannot be written.
+    pub fn spread_orders_to_async<W, I>(
+        &self,
+        out: &mut W,
+        orders: I,
+        format: OrdersFormat,
+    ) -> impl Future<Output = Result<usize, io::Error>> + Send
+    where
+        W: AsyncWrite + Unpin + Send + ?Sized,
+        I: IntoIterator,
+        I::Item: Into<Order>,
+    {
+        let mut formatted = vec![];
+        let written = self.spread_orders_to(&mut formatted, orders, format);
+        async move {
+            let written = written?;
+            out.write_all(&formatted).await?;
+            out.flush().await?;
+            Ok(written)
+        }
+    }
+
+    /// Reads the orders written in plain text to the file in the given path, checking that they
+    /// were given by this supervillain.
+    ///
+    /// # Errors
+    /// - `EvilError::Io` if the file cannot be opened or read.
+    /// - `EvilError::MalformedOrders` if the file isn't a list of orders from this supervillain.
+    pub fn read_orders_by_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<Order>, EvilError> {
+        let path = path.as_ref();
+        let buf_orders = open_buf_read(path).map_err(|err| io_error(path, &err))?;
+        self.read_orders_from(buf_orders)
+    }
+
+    /// Reads orders written in plain text from any source, checking that they were given by
+    /// this supervillain.
+    ///
+    /// # Errors
+    /// - `EvilError::Io` if the orders cannot be read.
+    /// - `EvilError::MalformedOrders` if they aren't a list of orders from this supervillain.
+    pub fn read_orders_from<R: BufRead>(&self, orders: R) -> Result<Vec<Order>, EvilError> {
+        parse_orders(orders, &self.full_name())
+    }
+
+    /// Returns the sidekick that finds the weak targets, or the outcome of stage 1 if there is
+    /// none.
+    fn stage1_sidekick(&self) -> Result<&Sidekick<'_>, StageOutcome> {
+        self.sidekick.as_ref().ok_or(StageOutcome::NoSidekick)
+    }
+}
+
+/// Writes the orders, without the header if they are appended to previous content.
+fn write_or_append_orders(
+    out: &mut dyn Write,
+    has_content: bool,
+    format: OrdersFormat,
+    leader: &str,
+    orders: impl IntoIterator<Item: Into<Order>>,
+) -> Result<usize, io::Error> {
+    if has_content {
+        format.append_orders(out, leader, orders)
+    } else {
+        format.write_orders(out, leader, orders)
+    }
+}
+
+fn headquarters_outcome<T, E: Display>(result: Result<T, E>, target: Location) -> StageOutcome {
+    match result {
+        Ok(_) => StageOutcome::HeadquartersBuilt {
+            location: target.name,
+        },
+        Err(err) => StageOutcome::HenchmanRefused {
+            reason: err.to_string(),
+        },
+    }
+}
+
+/// Returns the weak targets, or the outcome of stage 1 if there are none.
+fn weak_targets_found(targets: Vec<Location>) -> Result<Vec<Location>, StageOutcome> {
+    if targets.is_empty() {
+        return Err(StageOutcome::NoTargets);
+    }
+    Ok(targets)
+}
+
+/// Returns the weak target chosen by the selector, or the outcome of stage 1 if there is none.
+fn choose_target<S>(targets: &[Location], selector: &S) -> Result<Location, StageOutcome>
+where
+    S: TargetSelector + ?Sized,
+{
+    selector.select(targets).ok_or(StageOutcome::NoTargets)
+}
 
-            for order in orders {
-                writeln!(buf_orders, "- {order}")?;
-                orders_written += 1;
+/// Waits for the tokio tasks of the henchmen and returns their results in the order of their
+/// indexes. Henchmen whose task is cancelled fail with `HenchmanError::Cancelled`. If any task
+/// panics, the remaining ones are aborted and its panic is resumed.
+async fn joined_in_order(
+    mut tasks: JoinSet<(usize, Result<Vec<Outcome>, HenchmanError>)>,
+    count: usize,
+) -> Vec<Result<Vec<Outcome>, HenchmanError>> {
+    let mut results = vec![Err(HenchmanError::Cancelled); count];
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((index, result)) => results[index] = result,
+            Err(err) if err.is_panic() => {
+                tasks.abort_all();
+                panic::resume_unwind(err.into_panic())
             }
+            Err(_) => {}
+        }
+    }
+    results
+}
 
-            Ok(orders_written)
-        })
+async fn with_timeout<F>(timeout: Duration, task: Task, future: F) -> Result<Outcome, HenchmanError>
+where
+    F: Future<Output = Result<Outcome, HenchmanError>>,
+{
+    tokio::time::timeout(timeout, future)
+        .await
+        .unwrap_or(Err(HenchmanError::TimedOut { task, timeout }))
+}
+
+fn with_retries<F>(retries: usize, task: F) -> Result<Outcome, HenchmanError>
+where
+    F: Fn() -> Result<Outcome, HenchmanError>,
+{
+    let mut result = task();
+    for _ in 0..retries {
+        if result.is_ok() {
+            break;
+        }
+        result = task();
+    }
+    result
+}
+
+fn contains_weak_location<R: BufRead>(listing: R) -> Result<bool, EvilError> {
+    for location in parse_listing(listing) {
+        if location?.is_weak() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn io_error(path: &Path, err: &io::Error) -> EvilError {
+    EvilError::Io {
+        kind: err.kind().into(),
+        reason: format!("{}: {err}", path.display()),
     }
 }
 
@@ -188,74 +781,204 @@ impl TryFrom<&str> for Supervillain<'_> {
     type Error = EvilError;
 
     fn try_from(name: &str) -> Result<Self, Self::Error> {
-        let components = name.split(' ').collect::<Vec<_>>();
-        if components.len() < 2 {
-            Err(EvilError::ParseError {
-                purpose: "full_name".to_string(),
-                reason: "Too few arguments".to_string(),
-            })
-        } else {
-            Ok(Supervillain {
-                first_name: components[0].to_string(),
-                last_name: components[1].to_string(),
-                ..Default::default()
-            })
+        let mut supervillain = Supervillain::default();
+        supervillain.set_full_name(name)?;
+        Ok(supervillain)
+    }
+}
+
+/// Kinds of I/O errors, mirroring [`io::ErrorKind`] so that they can be serialized. The kinds
+/// that aren't mirrored become `Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IoErrorKind {
+    NotFound,
+    PermissionDenied,
+    AlreadyExists,
+    InvalidData,
+    UnexpectedEof,
+    Other,
+}
+
+impl From<io::ErrorKind> for IoErrorKind {
+    fn from(kind: io::ErrorKind) -> Self {
+        match kind {
+            io::ErrorKind::NotFound => IoErrorKind::NotFound,
+            io::ErrorKind::PermissionDenied => IoErrorKind::PermissionDenied,
+            io::ErrorKind::AlreadyExists => IoErrorKind::AlreadyExists,
+            io::ErrorKind::InvalidData => IoErrorKind::InvalidData,
+            io::ErrorKind::UnexpectedEof => IoErrorKind::UnexpectedEof,
+            _ => IoErrorKind::Other,
         }
     }
 }
 
+impl Display for IoErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            IoErrorKind::NotFound => "not found",
+            IoErrorKind::PermissionDenied => "permission denied",
+            IoErrorKind::AlreadyExists => "already exists",
+            IoErrorKind::InvalidData => "invalid data",
+            IoErrorKind::UnexpectedEof => "unexpected end of file",
+            IoErrorKind::Other => "other",
+        };
+        f.write_str(kind)
+    }
+}
+
 #[derive(Error, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EvilError {
     #[error("Parse error: purpose='{}', reason='{}'", .purpose, .reason)]
     ParseError { purpose: String, reason: String },
+    #[error("Cipher error: kind='{}', reason='{}'", .kind, .reason)]
+    CipherError {
+        kind: CipherErrorKind,
+        reason: String,
+    },
+    #[error("Not found: entity='{}', id={}", .entity, .id)]
+    NotFound { entity: String, id: u64 },
+    #[error("Storage error: reason='{}'", .reason)]
+    StorageError { reason: String },
+    #[error("Listing format error: line={}, reason='{}'", .line, .reason)]
+    ListingFormat { line: usize, reason: String },
+    #[error("Malformed orders: line={}, reason='{}'", .line, .reason)]
+    MalformedOrders { line: usize, reason: String },
+    #[error("I/O error: kind='{}', reason='{}'", .kind, .reason)]
+    Io { kind: IoErrorKind, reason: String },
+    #[error("Stage failed: stage='{}', reason='{}'", .stage, .reason)]
+    StageFailed { stage: String, reason: String },
 }
 
 mod aux {
     use std::{
-        cell::RefCell,
-        fs::File,
+        fs::{self, File, OpenOptions},
         io::{self, BufRead, BufReader, BufWriter, Write},
-        path::Path,
-        rc::Rc,
+        path::{Path, PathBuf},
     };
 
-    pub fn open_buf_read(path: &str) -> Option<impl BufRead> {
-        let Ok(mut file) = File::open(path) else {
-            return None;
-        };
-        Some(BufReader::new(file))
+    use tempfile::NamedTempFile;
+
+    use crate::orders::{WriteMode, WriteOptions};
+
+    pub fn open_buf_read<P: AsRef<Path>>(path: P) -> io::Result<impl BufRead> {
+        File::open(path).map(BufReader::new)
     }
 
-    pub fn open_write_execute<P, F>(path: P, operations: F) -> Result<usize, io::Error>
+    /// Opens the file as the write mode requires and runs the operations on it. They are told
+    /// whether they are appended to previous content.
+    pub fn open_write_execute<P, F>(
+        path: P,
+        options: WriteOptions,
+        operations: F,
+    ) -> Result<usize, io::Error>
     where
         P: AsRef<Path>,
-        F: FnOnce(Rc<RefCell<dyn Write>>) -> Result<usize, std::io::Error>,
+        F: FnOnce(&mut dyn Write, bool) -> Result<usize, std::io::Error>,
+    {
+        let path = path.as_ref();
+        let parent = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        if options.create_dirs {
+            fs::create_dir_all(parent)?;
+        }
+        match options.mode {
+            WriteMode::Overwrite => {
+                let temp_file = NamedTempFile::new_in(parent)?;
+                if let Ok(metadata) = fs::metadata(path) {
+                    temp_file
+                        .as_file()
+                        .set_permissions(metadata.permissions())?;
+                }
+                let (written, temp_file) =
+                    execute_buffered(temp_file, |out| operations(out, false))?;
+                temp_file.as_file().sync_all()?;
+                temp_file.persist(path).map_err(|err| err.error)?;
+                Ok(written)
+            }
+            WriteMode::Append => {
+                let file = OpenOptions::new().append(true).create(true).open(path)?;
+                let has_content = file.metadata()?.len() > 0;
+                execute_buffered(file, |out| operations(out, has_content))
+                    .map(|(written, _)| written)
+            }
+        }
+    }
+
+    pub async fn read_all_async(path: &Path) -> io::Result<Vec<u8>> {
+        tokio::fs::read(path).await
+    }
+
+    /// Same as [`open_write_execute`], but in a blocking task, so the same write modes are
+    /// honored without blocking the runtime.
+    pub async fn open_write_execute_async<F>(
+        path: PathBuf,
+        options: WriteOptions,
+        operations: F,
+    ) -> io::Result<usize>
+    where
+        F: FnOnce(&mut dyn Write, bool) -> Result<usize, std::io::Error> + Send + 'static,
+    {
+        tokio::task::spawn_blocking(move || open_write_execute(path, options, operations))
+            .await
+            .map_err(io::Error::other)?
+    }
+
+    /// Runs the operations on a buffered writer and returns the writer once it has been flushed.
+    fn execute_buffered<W, F>(writer: W, operations: F) -> Result<(usize, W), io::Error>
+    where
+        W: Write,
+        F: FnOnce(&mut dyn Write) -> Result<usize, std::io::Error>,
     {
-        let mut buffer = File::create(path)
-            .map(|file| Rc::new(RefCell::new(BufWriter::new(file))) as Rc<RefCell<dyn Write>>)?;
-        operations(buffer)
+        let mut buffer = BufWriter::new(writer);
+        let written = operations(&mut buffer)?;
+        let writer = buffer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?;
+        Ok((written, writer))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::cell::{Cell, RefCell};
+    use std::{
+        cell::{Cell, RefCell},
+        fs,
+    };
 
     use assertables::{
-        assert_err, assert_matches, assert_none, assert_ok_eq_x, assert_some, assert_some_eq_x,
+        assert_err, assert_matches, assert_none, assert_ok, assert_ok_eq_x, assert_some,
+        assert_some_eq_x,
     };
+    use async_trait::async_trait;
     use mockall::{Sequence, predicate::eq};
     use test_context::{AsyncTestContext, TestContext, test_context};
-
-    use crate::{cipher::MockCipher, gadget::MockGadget, henchman::MockHenchman, test_common};
+    use tokio::io::AsyncReadExt;
+
+    use crate::{
+        cipher::MockCipher,
+        crew::AssignmentPolicy,
+        gadget::{MockAsyncGadget, MockGadget},
+        henchman::{MockAsyncHenchman, MockHenchman, Task},
+        orders::{Priority, WriteMode},
+        plan::MockPlanner,
+        target::{MockTargetSelector, WeakestTarget},
+        test_common,
+    };
 
     use super::*;
 
     thread_local! {
         static FILE_IF_CAN_OPEN: RefCell<Option<doubles::File>> = const { RefCell::new(None) };
         static FILE_CAN_OPEN: Cell<bool> = const { Cell::new(false) };
-        static BUF_CONTENTS: RefCell<String> = const { RefCell::new(String::new()) };
+        static BUF_CONTENTS: RefCell<Vec<u8>> = const { RefCell::new(vec![]) };
         static BUF_WRITTEN: RefCell<Vec<u8>> = const { RefCell::new(vec![]) };
+        static WRITE_OPTIONS: Cell<Option<WriteOptions>> = const { Cell::new(None) };
+        static FILE_HAS_CONTENT: Cell<bool> = const { Cell::new(false) };
+        static OPENED_PATH: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
     }
 
     #[test_context(Context)]
@@ -273,7 +996,7 @@ mod tests {
     #[test_context(Context)]
     #[test]
     fn set_full_name_sets_first_and_last_names(ctx: &mut Context) {
-        ctx.sut.set_full_name(test_common::SECONDARY_FULL_NAME);
+        assert_ok!(ctx.sut.set_full_name(test_common::SECONDARY_FULL_NAME));
 
         // assert2::check!(ctx.sut.first_name == "A");
         // assert2::assert!(ctx.sut.last_name == "B");
@@ -283,20 +1006,39 @@ mod tests {
 
     #[test_context(Context)]
     #[test]
-    #[should_panic(expected = "Name must have first and last name")]
-    fn set_full_name_panics_with_empty_name(ctx: &mut Context) {
-        ctx.sut.set_full_name("");
+    fn set_full_name_keeps_title_middle_names_and_particles(ctx: &mut Context) {
+        assert_ok!(ctx.sut.set_full_name("Doctor  Victor Werner Von Doom"));
+
+        assert2::check!(ctx.sut.first_name == "Doctor Victor Werner");
+        assert2::assert!(ctx.sut.last_name == "Von Doom");
     }
 
+    #[test_context(Context)]
     #[test]
-    fn try_from_str_slice_produces_supervillain_full_with_first_and_last_name()
-    -> Result<(), EvilError> {
+    fn set_full_name_fails_with_empty_name_and_keeps_names(ctx: &mut Context) {
+        let result = ctx.sut.set_full_name("");
+
+        assert_matches!(result, Err(EvilError::ParseError { purpose, reason }) if purpose == "full_name" && reason == "Too few arguments");
+        assert_eq!(ctx.sut.full_name(), test_common::PRIMARY_FULL_NAME);
+    }
+
+    #[test]
+    fn try_from_str_slice_produces_supervillain_full_with_first_and_last_name()
+    -> Result<(), EvilError> {
         let sut = Supervillain::try_from(test_common::SECONDARY_FULL_NAME)?;
         assert_eq!(sut.first_name, test_common::SECONDARY_FIRST_NAME);
         assert_eq!(sut.last_name, test_common::SECONDARY_LAST_NAME);
         Ok(())
     }
 
+    #[test]
+    fn try_from_str_slice_keeps_every_component_of_the_name() -> Result<(), EvilError> {
+        let sut = Supervillain::try_from("Doctor Victor Von Doom")?;
+        assert_eq!(sut.first_name, "Doctor Victor");
+        assert_eq!(sut.last_name, "Von Doom");
+        Ok(())
+    }
+
     #[test]
     fn try_from_str_slice_produces_error_with_less_than_two_substrings() {
         let result = Supervillain::try_from("");
@@ -326,8 +1068,58 @@ mod tests {
 
     #[test_context(Context)]
     #[tokio::test]
-    async fn plan_is_sadly_expected(ctx: &mut Context<'_>) {
-        assert_eq!(ctx.sut.come_up_with_plan().await, "Take over the world!");
+    async fn plan_uses_weak_locations_of_the_listing_and_sidekick(ctx: &mut Context<'_>) {
+        FILE_CAN_OPEN.set(true);
+        BUF_CONTENTS.replace(Vec::from(
+            r"Madrid,strong
+              Las Vegas,weak",
+        ));
+        ctx.sut.sidekick = Some(Sidekick::new());
+        let expected_plan = Plan {
+            goal: String::from("Conquer Las Vegas"),
+            steps: vec![String::from("Conquer Las Vegas!")],
+        };
+        let mut planner = MockPlanner::new();
+        planner
+            .expect_plan()
+            .withf(|resources| {
+                resources.sidekick
+                    && resources.henchmen == 3
+                    && resources.weak_locations.len() == 1
+                    && resources.weak_locations[0].name == "Las Vegas"
+            })
+            .once()
+            .return_const(expected_plan.clone());
+        let resources = Resources {
+            henchmen: 3,
+            ..Resources::default()
+        };
+
+        let plan = ctx.sut.come_up_with_plan(&mut planner, resources).await;
+
+        assert_eq!(plan, expected_plan);
+    }
+
+    #[test_context(Context)]
+    #[tokio::test]
+    async fn plan_without_listing_has_no_weak_locations(ctx: &mut Context<'_>) {
+        FILE_CAN_OPEN.set(false);
+        let mut planner = MockPlanner::new();
+        planner
+            .expect_plan()
+            .withf(|resources| !resources.sidekick && resources.weak_locations.is_empty())
+            .once()
+            .return_const(Plan {
+                goal: String::from("Steal the moon"),
+                steps: vec![],
+            });
+
+        let plan = ctx
+            .sut
+            .come_up_with_plan(&mut planner, Resources::default())
+            .await;
+
+        assert_eq!(plan.goal, "Steal the moon");
     }
 
     #[test_context(Context)]
@@ -370,225 +1162,1349 @@ mod tests {
         mock_henchman
             .expect_build_secret_hq()
             .with(eq(String::from(test_common::FIRST_TARGET)))
-            .return_const(());
+            .returning(|location| Ok(test_common::outcome(Task::BuildSecretHq { location })));
         let mut mock_sidekick = Sidekick::new();
         mock_sidekick
             .expect_get_weak_targets()
             .once()
-            .returning(|_| test_common::TARGETS.map(String::from).to_vec());
+            .returning(|_| test_common::TARGETS.map(Location::from).to_vec());
         ctx.sut.sidekick = Some(mock_sidekick);
 
-        ctx.sut
+        let outcome = ctx
+            .sut
             .start_world_domination_stage1(&mut mock_henchman, &gdummy);
+
+        assert_eq!(
+            outcome,
+            StageOutcome::HeadquartersBuilt {
+                location: String::from(test_common::FIRST_TARGET)
+            }
+        );
     }
 
     #[test_context(Context)]
     #[test]
-    fn world_domination_stage2_tells_henchman_to_do_hard_things_and_fight_with_enemies(
-        ctx: &mut Context,
-    ) {
+    fn world_domination_stage1_uses_target_selector_of_supervillain(ctx: &mut Context) {
         let mut mock_henchman = MockHenchman::new();
-        let mut sequence = Sequence::new();
-        mock_henchman
-            .expect_fight_enemies()
-            .once()
-            .in_sequence(&mut sequence)
-            .return_const(());
         mock_henchman
-            .expect_do_hard_things()
+            .expect_build_secret_hq()
+            .with(eq(String::from("Vilnius")))
             .once()
-            .in_sequence(&mut sequence)
-            .return_const(());
+            .returning(|location| Ok(test_common::outcome(Task::BuildSecretHq { location })));
+        let mut mock_sidekick = Sidekick::new();
+        mock_sidekick.expect_get_weak_targets().returning(|_| {
+            vec![
+                Location {
+                    score: Some(9),
+                    ..Location::from("Tampa")
+                },
+                Location {
+                    score: Some(2),
+                    ..Location::from("Vilnius")
+                },
+            ]
+        });
+        ctx.sut.sidekick = Some(mock_sidekick);
+        ctx.sut.target_selector = Some(Box::new(WeakestTarget));
 
-        ctx.sut.start_world_domination_stage2(&mock_henchman);
+        let outcome = ctx
+            .sut
+            .start_world_domination_stage1(&mut mock_henchman, &MockGadget::new());
+
+        assert_eq!(
+            outcome,
+            StageOutcome::HeadquartersBuilt {
+                location: String::from("Vilnius")
+            }
+        );
     }
 
     #[test_context(Context)]
     #[test]
-    fn tell_plans_sends_ciphered_message(ctx: &mut Context) {
+    fn world_domination_stage1_with_uses_provided_target_selector(ctx: &mut Context) {
+        let mut mock_henchman = MockHenchman::new();
+        mock_henchman
+            .expect_build_secret_hq()
+            .with(eq(String::from("Pamplona")))
+            .once()
+            .returning(|location| Ok(test_common::outcome(Task::BuildSecretHq { location })));
         let mut mock_sidekick = Sidekick::new();
         mock_sidekick
-            .expect_tell()
-            .with(eq(String::from(test_common::MAIN_CIPHERED_MESSAGE)))
-            .once()
-            .return_const(());
+            .expect_get_weak_targets()
+            .returning(|_| test_common::TARGETS.map(Location::from).to_vec());
         ctx.sut.sidekick = Some(mock_sidekick);
-        let mut mock_cipher = MockCipher::new();
-        mock_cipher
-            .expect_transform()
-            .returning(|secret, _| String::from("+") + secret + "+");
+        let mut mock_selector = MockTargetSelector::new();
+        mock_selector
+            .expect_select()
+            .withf(|targets| targets.len() == test_common::TARGETS.len())
+            .once()
+            .returning(|targets| targets.get(1).cloned());
 
-        ctx.sut
-            .tell_plans(test_common::MAIN_SECRET_MESSAGE, &mock_cipher);
+        let outcome = ctx.sut.start_world_domination_stage1_with(
+            &mut mock_henchman,
+            &MockGadget::new(),
+            &mock_selector,
+        );
+
+        assert_eq!(
+            outcome,
+            StageOutcome::HeadquartersBuilt {
+                location: String::from("Pamplona")
+            }
+        );
     }
 
     #[test_context(Context)]
     #[test]
-    fn vulnerable_locations_with_no_file_returns_none(ctx: &mut Context) {
-        FILE_IF_CAN_OPEN.replace(None);
-        assert_none!(ctx.sut.are_there_vulnerable_locations());
+    fn world_domination_stage1_without_sidekick_reports_it(ctx: &mut Context) {
+        let mut mock_henchman = MockHenchman::new();
+        mock_henchman.expect_build_secret_hq().never();
+
+        let outcome = ctx
+            .sut
+            .start_world_domination_stage1(&mut mock_henchman, &MockGadget::new());
+
+        assert_eq!(outcome, StageOutcome::NoSidekick);
     }
 
     #[test_context(Context)]
     #[test]
-    fn vulnerable_locations_with_file_reading_error_returns_none(ctx: &mut Context) {
-        FILE_IF_CAN_OPEN.replace(Some(doubles::File::new(None)));
-        assert_none!(ctx.sut.are_there_vulnerable_locations());
+    fn world_domination_stage1_without_targets_reports_it(ctx: &mut Context) {
+        let mut mock_henchman = MockHenchman::new();
+        mock_henchman.expect_build_secret_hq().never();
+        let mut mock_sidekick = Sidekick::new();
+        mock_sidekick
+            .expect_get_weak_targets()
+            .once()
+            .returning(|_| vec![]);
+        ctx.sut.sidekick = Some(mock_sidekick);
+
+        let outcome = ctx
+            .sut
+            .start_world_domination_stage1(&mut mock_henchman, &MockGadget::new());
+
+        assert_eq!(outcome, StageOutcome::NoTargets);
     }
 
     #[test_context(Context)]
     #[test]
-    fn vulnerable_locations_with_weak_returns_true(ctx: &mut Context) {
-        FILE_IF_CAN_OPEN.replace(Some(doubles::File::new(Some(String::from(
-            r"Madrid,strong
-              Las Vegas,weak
-              New York,strong",
-        )))));
-        assert_some_eq_x!(ctx.sut.are_there_vulnerable_locations(), true);
+    fn world_domination_stage2_tells_henchman_to_do_hard_things_and_fight_with_enemies(
+        ctx: &mut Context,
+    ) {
+        let mut mock_henchman = MockHenchman::new();
+        let mut sequence = Sequence::new();
+        mock_henchman
+            .expect_fight_enemies()
+            .once()
+            .in_sequence(&mut sequence)
+            .returning(|| Ok(test_common::outcome(Task::FightEnemies)));
+        mock_henchman
+            .expect_do_hard_things()
+            .once()
+            .in_sequence(&mut sequence)
+            .returning(|| Ok(test_common::outcome(Task::DoHardThings)));
+
+        let outcomes = ctx.sut.start_world_domination_stage2(&mock_henchman);
+
+        assert_eq!(outcomes.map(|outcomes| outcomes.len()), Ok(2));
     }
 
     #[test_context(Context)]
     #[test]
-    fn vulnerable_locations_without_weak_returns_false(ctx: &mut Context) {
-        FILE_IF_CAN_OPEN.replace(Some(doubles::File::new(Some(String::from(
-            r"Madrid,strong
-              Oregon,strong
-              New York,strong",
-        )))));
-        assert_some_eq_x!(ctx.sut.are_there_vulnerable_locations(), false);
+    fn world_domination_stage2_stops_if_henchman_cannot_fight(ctx: &mut Context) {
+        let mut mock_henchman = MockHenchman::new();
+        mock_henchman
+            .expect_fight_enemies()
+            .once()
+            .returning(|| Err(exhausted()));
+        mock_henchman.expect_do_hard_things().never();
+
+        let outcomes = ctx.sut.start_world_domination_stage2(&mock_henchman);
+
+        assert_eq!(outcomes, Err(exhausted()));
     }
 
     #[test_context(Context)]
     #[test]
-    fn efficient_vulnerable_locations_with_no_file_returns_none(ctx: &mut Context) {
-        FILE_CAN_OPEN.set(false);
-        assert_none!(ctx.sut.are_there_vulnerable_locations_efficient());
+    fn world_domination_stage2_retries_failed_tasks(ctx: &mut Context) {
+        let mut mock_henchman = MockHenchman::new();
+        let mut sequence = Sequence::new();
+        mock_henchman
+            .expect_fight_enemies()
+            .times(2)
+            .in_sequence(&mut sequence)
+            .returning(|| Err(exhausted()));
+        mock_henchman
+            .expect_fight_enemies()
+            .once()
+            .in_sequence(&mut sequence)
+            .returning(|| Ok(test_common::outcome(Task::FightEnemies)));
+        mock_henchman
+            .expect_do_hard_things()
+            .once()
+            .in_sequence(&mut sequence)
+            .returning(|| Ok(test_common::outcome(Task::DoHardThings)));
+
+        let outcomes = ctx
+            .sut
+            .start_world_domination_stage2_with_retries(&mock_henchman, 2);
+
+        assert_eq!(outcomes.map(|outcomes| outcomes.len()), Ok(2));
     }
 
     #[test_context(Context)]
     #[test]
-    fn efficient_vulnerable_locations_with_weak_returns_true(ctx: &mut Context) {
-        FILE_CAN_OPEN.set(true);
-        BUF_CONTENTS.replace(String::from(
-            r"Madrid,strong
-              Las Vegas,weak
-              New York,strong",
-        ));
-        assert_some_eq_x!(ctx.sut.are_there_vulnerable_locations_efficient(), true);
+    fn world_domination_stage1_reports_henchman_refusal(ctx: &mut Context) {
+        let mut mock_henchman = MockHenchman::new();
+        mock_henchman
+            .expect_build_secret_hq()
+            .once()
+            .returning(|_| Err(exhausted()));
+        let mut mock_sidekick = Sidekick::new();
+        mock_sidekick
+            .expect_get_weak_targets()
+            .returning(|_| test_common::TARGETS.map(Location::from).to_vec());
+        ctx.sut.sidekick = Some(mock_sidekick);
+
+        let outcome = ctx
+            .sut
+            .start_world_domination_stage1(&mut mock_henchman, &MockGadget::new());
+
+        assert_eq!(
+            outcome,
+            StageOutcome::HenchmanRefused {
+                reason: exhausted().to_string()
+            }
+        );
     }
 
     #[test_context(Context)]
     #[test]
-    fn efficient_vulnerable_locations_without_weak_returns_false(ctx: &mut Context) {
-        FILE_CAN_OPEN.set(true);
-        BUF_CONTENTS.replace(String::from(
-            r"Madrid,strong
-              Oregon,strong
-              New York,strong",
-        ));
-        assert_some_eq_x!(ctx.sut.are_there_vulnerable_locations_efficient(), false);
+    fn world_domination_stage1_with_crew_builds_hq_if_any_member_can(ctx: &mut Context) {
+        let mut crew = Crew::new(AssignmentPolicy::AllHands);
+        let mut refusing = MockHenchman::new();
+        refusing
+            .expect_build_secret_hq()
+            .once()
+            .returning(|_| Err(exhausted()));
+        let mut builder = MockHenchman::new();
+        builder
+            .expect_build_secret_hq()
+            .with(eq(String::from(test_common::FIRST_TARGET)))
+            .once()
+            .returning(|location| Ok(test_common::outcome(Task::BuildSecretHq { location })));
+        crew.push(Box::new(refusing));
+        crew.push(Box::new(builder));
+        let mut mock_sidekick = Sidekick::new();
+        mock_sidekick
+            .expect_get_weak_targets()
+            .returning(|_| test_common::TARGETS.map(Location::from).to_vec());
+        ctx.sut.sidekick = Some(mock_sidekick);
+
+        let outcome = ctx
+            .sut
+            .start_world_domination_stage1_with_crew(&mut crew, &MockGadget::new());
+
+        assert_eq!(
+            outcome,
+            StageOutcome::HeadquartersBuilt {
+                location: String::from(test_common::FIRST_TARGET)
+            }
+        );
     }
 
     #[test_context(Context)]
     #[test]
-    fn spread_orders_by_file_returns_error_on_failure(ctx: &mut Context) {
-        FILE_CAN_OPEN.set(false);
-        assert_err!(ctx.sut.spread_orders_by_file("some/path", vec![]));
+    fn world_domination_stage1_with_empty_crew_reports_refusal(ctx: &mut Context) {
+        let mut mock_sidekick = Sidekick::new();
+        mock_sidekick
+            .expect_get_weak_targets()
+            .returning(|_| test_common::TARGETS.map(Location::from).to_vec());
+        ctx.sut.sidekick = Some(mock_sidekick);
+
+        let outcome = ctx.sut.start_world_domination_stage1_with_crew(
+            &mut Crew::new(AssignmentPolicy::RoundRobin),
+            &MockGadget::new(),
+        );
+
+        assert_eq!(
+            outcome,
+            StageOutcome::HenchmanRefused {
+                reason: CrewError::Empty.to_string()
+            }
+        );
     }
 
     #[test_context(Context)]
     #[test]
-    fn spread_orders_by_file_writes_provided_orders(ctx: &mut Context) {
-        FILE_CAN_OPEN.set(true);
-        let orders = vec![
-            "Build headquarters".to_string(),
-            "Fight enemies".to_string(),
-            "Conquer the world".to_string(),
-        ];
-        let expected_message = concat!(
-            "I, Lex Luthor, as your leader, tell you to:\n",
-            "- Build headquarters\n",
-            "- Fight enemies\n",
-            "- Conquer the world\n"
-        );
+    fn world_domination_stage2_with_crew_shares_tasks_in_turns(ctx: &mut Context) {
+        let mut crew = Crew::new(AssignmentPolicy::RoundRobin);
+        let mut fighter = MockHenchman::new();
+        fighter
+            .expect_fight_enemies()
+            .once()
+            .returning(|| Ok(test_common::outcome(Task::FightEnemies)));
+        fighter.expect_do_hard_things().never();
+        let mut doer = MockHenchman::new();
+        doer.expect_fight_enemies().never();
+        doer.expect_do_hard_things()
+            .once()
+            .returning(|| Ok(test_common::outcome(Task::DoHardThings)));
+        crew.push(Box::new(fighter));
+        crew.push(Box::new(doer));
 
-        assert_ok_eq_x!(ctx.sut.spread_orders_by_file("some/path", orders), 3);
-        let actual_message = BUF_WRITTEN.take();
-        assert_ok_eq_x!(str::from_utf8(&actual_message), expected_message);
-    }
+        let reports = ctx.sut.start_world_domination_stage2_with_crew(&mut crew);
 
-    struct Context<'a> {
-        sut: Supervillain<'a>,
+        assert_eq!(
+            reports.map(|reports| reports.into_iter().map(|report| report.task).collect()),
+            Ok(vec![Task::FightEnemies, Task::DoHardThings])
+        );
     }
 
-    impl<'a> AsyncTestContext for Context<'a> {
-        async fn setup() -> Context<'a> {
-            Context {
-                sut: Supervillain {
-                    first_name: test_common::PRIMARY_FIRST_NAME.to_string(),
-                    last_name: test_common::PRIMARY_LAST_NAME.to_string(),
-                    ..Default::default()
-                },
-            }
+    #[test_context(Context)]
+    #[test]
+    fn world_domination_stage2_with_crew_stops_if_nobody_can_fight(ctx: &mut Context) {
+        let mut crew = Crew::new(AssignmentPolicy::AllHands);
+        for _ in 0..2 {
+            let mut henchman = MockHenchman::new();
+            henchman
+                .expect_fight_enemies()
+                .once()
+                .returning(|| Err(exhausted()));
+            henchman.expect_do_hard_things().never();
+            crew.push(Box::new(henchman));
         }
 
-        async fn teardown(self) {}
+        let reports = ctx.sut.start_world_domination_stage2_with_crew(&mut crew);
+
+        assert_eq!(reports, Err(CrewError::Henchman(exhausted())));
     }
 
-    pub mod doubles {
-        use std::io::{self, Cursor, Error, ErrorKind};
+    fn exhausted() -> HenchmanError {
+        HenchmanError::Exhausted {
+            name: String::from("Bob"),
+            stamina: 0,
+            required: 25,
+        }
+    }
 
-        use super::*;
+    /// Henchman that takes its time to perform every task.
+    struct SlowHenchman {
+        delay: Duration,
+    }
 
-        pub struct File {
-            read_result: Option<String>,
+    #[async_trait]
+    impl AsyncHenchman for SlowHenchman {
+        async fn build_secret_hq(&self, location: String) -> Result<Outcome, HenchmanError> {
+            tokio::time::sleep(self.delay).await;
+            Ok(test_common::outcome(Task::BuildSecretHq { location }))
         }
 
-        impl File {
-            pub fn new(read_result: Option<String>) -> File {
-                File { read_result }
-            }
+        async fn do_hard_things(&self) -> Result<Outcome, HenchmanError> {
+            tokio::time::sleep(self.delay).await;
+            Ok(test_common::outcome(Task::DoHardThings))
+        }
 
-            pub fn open<P: AsRef<Path>>(path: P) -> io::Result<File> {
-                if let Some(file) = FILE_IF_CAN_OPEN.take() {
-                    Ok(file)
-                } else {
-                    Err(Error::from(ErrorKind::NotFound))
-                }
-            }
-            pub fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
-                if let Some(ref content) = self.read_result {
-                    *buf = content.to_owned();
-                    Ok(buf.len())
-                } else {
-                    Err(Error::from(ErrorKind::Other))
-                }
-            }
+        async fn fight_enemies(&self) -> Result<Outcome, HenchmanError> {
+            tokio::time::sleep(self.delay).await;
+            Ok(test_common::outcome(Task::FightEnemies))
         }
+    }
 
-        pub fn open_buf_read(path: &str) -> Option<impl BufRead> {
-            if FILE_CAN_OPEN.get() {
-                Some(Cursor::new(BUF_CONTENTS.take()))
-            } else {
-                None
-            }
+    fn slow(millis: u64) -> Arc<dyn AsyncHenchman> {
+        Arc::new(SlowHenchman {
+            delay: Duration::from_millis(millis),
+        })
+    }
+
+    fn sidekick_with_targets<'a>() -> Sidekick<'a> {
+        let mut mock_sidekick = Sidekick::new();
+        mock_sidekick
+            .expect_get_weak_targets_async()
+            .once()
+            .returning(|_| test_common::TARGETS.map(Location::from).to_vec());
+        mock_sidekick
+    }
+
+    #[test_context(Context)]
+    #[tokio::test]
+    async fn async_world_domination_stage1_builds_hq_in_first_weak_target(ctx: &mut Context<'_>) {
+        let mut mock_henchman = MockAsyncHenchman::new();
+        mock_henchman
+            .expect_build_secret_hq()
+            .with(eq(String::from(test_common::FIRST_TARGET)))
+            .once()
+            .returning(|location| Ok(test_common::outcome(Task::BuildSecretHq { location })));
+        ctx.sut.sidekick = Some(sidekick_with_targets());
+
+        let outcome = ctx
+            .sut
+            .start_world_domination_stage1_async(
+                &mock_henchman,
+                &MockAsyncGadget::new(),
+                Duration::from_secs(1),
+            )
+            .await;
+
+        assert_eq!(
+            outcome,
+            StageOutcome::HeadquartersBuilt {
+                location: String::from(test_common::FIRST_TARGET)
+            }
+        );
+    }
+
+    #[test_context(Context)]
+    #[tokio::test(start_paused = true)]
+    async fn async_world_domination_stage1_refuses_if_henchman_times_out(ctx: &mut Context<'_>) {
+        let timeout = Duration::from_millis(100);
+        ctx.sut.sidekick = Some(sidekick_with_targets());
+
+        let outcome = ctx
+            .sut
+            .start_world_domination_stage1_async(
+                slow(500).as_ref(),
+                &MockAsyncGadget::new(),
+                timeout,
+            )
+            .await;
+
+        let timed_out = HenchmanError::TimedOut {
+            task: Task::BuildSecretHq {
+                location: String::from(test_common::FIRST_TARGET),
+            },
+            timeout,
+        };
+        assert_eq!(
+            outcome,
+            StageOutcome::HenchmanRefused {
+                reason: timed_out.to_string()
+            }
+        );
+    }
+
+    #[test_context(Context)]
+    #[tokio::test(start_paused = true)]
+    async fn async_world_domination_stage2_runs_henchmen_concurrently(ctx: &mut Context<'_>) {
+        let henchmen = [slow(100), slow(100), slow(100)];
+        let start = tokio::time::Instant::now();
+
+        let results = ctx
+            .sut
+            .start_world_domination_stage2_async(&henchmen, Duration::from_secs(1))
+            .await;
+
+        assert_eq!(start.elapsed(), Duration::from_millis(200));
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(Result::is_ok));
+    }
+
+    #[test_context(Context)]
+    #[tokio::test(start_paused = true)]
+    async fn async_world_domination_stage2_stops_only_henchmen_that_time_out(
+        ctx: &mut Context<'_>,
+    ) {
+        let timeout = Duration::from_millis(100);
+
+        let results = ctx
+            .sut
+            .start_world_domination_stage2_async(&[slow(10), slow(500)], timeout)
+            .await;
+
+        assert_eq!(results[0].as_ref().map(Vec::len), Ok(2));
+        assert_eq!(
+            results[1],
+            Err(HenchmanError::TimedOut {
+                task: Task::FightEnemies,
+                timeout
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn cancelled_henchman_task_fails() {
+        let mut tasks = JoinSet::new();
+        tasks.spawn(async { (0, Ok(vec![])) });
+        tasks.spawn(std::future::pending()).abort();
+
+        assert_eq!(
+            joined_in_order(tasks, 2).await,
+            [Ok(vec![]), Err(HenchmanError::Cancelled)]
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Henchman panicked")]
+    async fn panicked_henchman_task_panics_again() {
+        let mut tasks = JoinSet::new();
+        tasks.spawn(async { panic!("Henchman panicked") });
+
+        joined_in_order(tasks, 1).await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn panicked_henchman_aborts_the_others() {
+        let mut mock_henchman = MockAsyncHenchman::new();
+        mock_henchman
+            .expect_fight_enemies()
+            .returning(|| panic!("Henchman panicked"));
+        let slow_henchman = slow(1000);
+        let henchmen: [Arc<dyn AsyncHenchman>; 2] =
+            [Arc::new(mock_henchman), Arc::clone(&slow_henchman)];
+
+        let joined = tokio::spawn(async move {
+            Supervillain::default()
+                .start_world_domination_stage2_async(&henchmen, Duration::from_secs(5))
+                .await
+        })
+        .await;
+        tokio::task::yield_now().await;
+
+        assert!(joined.is_err_and(|err| err.is_panic()));
+        assert_eq!(Arc::strong_count(&slow_henchman), 1);
+    }
+
+    #[test_context(Context)]
+    #[tokio::test]
+    async fn async_world_domination_stage1_without_sidekick_reports_it(ctx: &mut Context<'_>) {
+        let outcome = ctx
+            .sut
+            .start_world_domination_stage1_async(
+                &MockAsyncHenchman::new(),
+                &MockAsyncGadget::new(),
+                Duration::from_secs(1),
+            )
+            .await;
+
+        assert_eq!(outcome, StageOutcome::NoSidekick);
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn tell_plans_sends_ciphered_message(ctx: &mut Context) {
+        let mut mock_sidekick = Sidekick::new();
+        mock_sidekick
+            .expect_tell()
+            .with(eq(String::from(test_common::MAIN_CIPHERED_MESSAGE)))
+            .once()
+            .returning(|_| Ok(()));
+        ctx.sut.sidekick = Some(mock_sidekick);
+        let mut mock_cipher = MockCipher::new();
+        mock_cipher
+            .expect_transform()
+            .returning(|secret, _| Ok(String::from("+") + secret + "+"));
+
+        assert_ok!(
+            ctx.sut
+                .tell_plans(test_common::MAIN_SECRET_MESSAGE, &mock_cipher)
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn tell_plans_propagates_cipher_error_without_telling_sidekick(ctx: &mut Context) {
+        let mut mock_sidekick = Sidekick::new();
+        mock_sidekick.expect_tell().never();
+        ctx.sut.sidekick = Some(mock_sidekick);
+        let mut mock_cipher = MockCipher::new();
+        mock_cipher.expect_transform().returning(|_, _| {
+            Err(EvilError::CipherError {
+                kind: CipherErrorKind::InvalidKey,
+                reason: String::from("Key must not be empty"),
+            })
+        });
+
+        let result = ctx
+            .sut
+            .tell_plans(test_common::MAIN_SECRET_MESSAGE, &mock_cipher);
+
+        assert_matches!(
+            result,
+            Err(EvilError::CipherError {
+                kind: CipherErrorKind::InvalidKey,
+                ..
+            })
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn tell_plans_without_sidekick_doesnt_cipher(ctx: &mut Context) {
+        let mut mock_cipher = MockCipher::new();
+        mock_cipher.expect_transform().never();
+
+        assert_ok!(
+            ctx.sut
+                .tell_plans(test_common::MAIN_SECRET_MESSAGE, &mock_cipher)
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn vulnerable_locations_with_no_file_returns_io_error(ctx: &mut Context) {
+        FILE_IF_CAN_OPEN.replace(None);
+        assert_matches!(
+            ctx.sut.are_there_vulnerable_locations(),
+            Err(EvilError::Io {
+                kind: IoErrorKind::NotFound,
+                ..
+            })
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn vulnerable_locations_with_file_reading_error_returns_io_error(ctx: &mut Context) {
+        FILE_IF_CAN_OPEN.replace(Some(doubles::File::new(None)));
+        assert_matches!(
+            ctx.sut.are_there_vulnerable_locations(),
+            Err(EvilError::Io {
+                kind: IoErrorKind::Other,
+                ..
+            })
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn vulnerable_locations_with_weak_returns_true(ctx: &mut Context) {
+        FILE_IF_CAN_OPEN.replace(Some(doubles::File::new(Some(String::from(
+            r"Madrid,strong
+              Las Vegas,weak
+              New York,strong",
+        )))));
+        assert_ok_eq_x!(ctx.sut.are_there_vulnerable_locations(), true);
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn vulnerable_locations_without_weak_returns_false(ctx: &mut Context) {
+        FILE_IF_CAN_OPEN.replace(Some(doubles::File::new(Some(String::from(
+            r"Madrid,strong
+              Oregon,strong
+              New York,strong",
+        )))));
+        assert_ok_eq_x!(ctx.sut.are_there_vulnerable_locations(), false);
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn vulnerable_locations_with_malformed_line_returns_its_line_number(ctx: &mut Context) {
+        FILE_IF_CAN_OPEN.replace(Some(doubles::File::new(Some(String::from(
+            r"Madrid,strong
+              Atlantis
+              Las Vegas,weak",
+        )))));
+        assert_matches!(
+            ctx.sut.are_there_vulnerable_locations(),
+            Err(EvilError::ListingFormat { line: 2, .. })
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn efficient_vulnerable_locations_with_no_file_returns_io_error(ctx: &mut Context) {
+        FILE_CAN_OPEN.set(false);
+        assert_matches!(
+            ctx.sut.are_there_vulnerable_locations_efficient(),
+            Err(EvilError::Io {
+                kind: IoErrorKind::NotFound,
+                ..
+            })
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn efficient_vulnerable_locations_with_weak_returns_true(ctx: &mut Context) {
+        FILE_CAN_OPEN.set(true);
+        BUF_CONTENTS.replace(Vec::from(
+            r"Madrid,strong
+              Las Vegas,weak
+              New York,strong",
+        ));
+        assert_ok_eq_x!(ctx.sut.are_there_vulnerable_locations_efficient(), true);
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn efficient_vulnerable_locations_without_weak_returns_false(ctx: &mut Context) {
+        FILE_CAN_OPEN.set(true);
+        BUF_CONTENTS.replace(Vec::from(
+            r"Madrid,strong
+              Oregon,strong
+              New York,strong",
+        ));
+        assert_ok_eq_x!(ctx.sut.are_there_vulnerable_locations_efficient(), false);
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn efficient_vulnerable_locations_with_undecodable_line_returns_its_line_number(
+        ctx: &mut Context,
+    ) {
+        FILE_CAN_OPEN.set(true);
+        BUF_CONTENTS.replace(b"Madrid,strong\nLas \xff Vegas,weak\n".to_vec());
+        assert_matches!(
+            ctx.sut.are_there_vulnerable_locations_efficient(),
+            Err(EvilError::ListingFormat { line: 2, .. })
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn vulnerable_locations_reads_default_listing_path(ctx: &mut Context) {
+        FILE_IF_CAN_OPEN.replace(None);
+
+        let _ = ctx.sut.are_there_vulnerable_locations();
+
+        assert_eq!(OPENED_PATH.take(), Some(PathBuf::from(LISTING_PATH)));
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn vulnerable_locations_reads_configured_listing_path(ctx: &mut Context) {
+        FILE_IF_CAN_OPEN.replace(None);
+        ctx.sut.listing_path = Some(PathBuf::from("/var/evil/listing.csv"));
+
+        let _ = ctx.sut.are_there_vulnerable_locations();
+
+        assert_eq!(
+            OPENED_PATH.take(),
+            Some(PathBuf::from("/var/evil/listing.csv"))
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn efficient_vulnerable_locations_reads_configured_listing_path(ctx: &mut Context) {
+        FILE_CAN_OPEN.set(false);
+        ctx.sut.listing_path = Some(PathBuf::from("/var/evil/listing.csv"));
+
+        let _ = ctx.sut.are_there_vulnerable_locations_efficient();
+
+        assert_eq!(
+            OPENED_PATH.take(),
+            Some(PathBuf::from("/var/evil/listing.csv"))
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn weak_locations_from_reads_provided_listing(ctx: &mut Context) {
+        let listing = "Madrid,strong\nLas Vegas,weak\n";
+
+        let locations = ctx.sut.weak_locations_from(listing.as_bytes()).unwrap();
+
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].name, "Las Vegas");
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn weak_locations_from_stops_at_read_error(ctx: &mut Context) {
+        struct BrokenReader;
+        impl Read for BrokenReader {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("Disk failure"))
+            }
+        }
+        let listing = io::BufReader::new(Read::chain("Las Vegas,weak\n".as_bytes(), BrokenReader));
+
+        assert_matches!(
+            ctx.sut.weak_locations_from(listing),
+            Err(EvilError::Io {
+                kind: IoErrorKind::Other,
+                ..
+            })
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn weak_locations_with_no_file_returns_not_found(ctx: &mut Context) {
+        FILE_CAN_OPEN.set(false);
+        assert_matches!(
+            ctx.sut.weak_locations(),
+            Err(EvilError::Io {
+                kind: IoErrorKind::NotFound,
+                ..
+            })
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn weak_locations_returns_only_weak_ones_skipping_malformed_lines(ctx: &mut Context) {
+        FILE_CAN_OPEN.set(true);
+        BUF_CONTENTS.replace(Vec::from(
+            r"Madrid,strong
+              Las Vegas,weak
+              Atlantis
+              Tampa,weak",
+        ));
+
+        let names = ctx.sut.weak_locations().map(|locations| {
+            locations
+                .into_iter()
+                .map(|location| location.name)
+                .collect::<Vec<_>>()
+        });
+
+        assert_eq!(
+            names.ok(),
+            Some(vec![String::from("Las Vegas"), String::from("Tampa")])
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn spread_orders_by_file_returns_error_on_failure(ctx: &mut Context) {
+        FILE_CAN_OPEN.set(false);
+        assert_err!(
+            ctx.sut
+                .spread_orders_by_file("some/path", Vec::<Order>::new())
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn spread_orders_by_file_writes_provided_orders(ctx: &mut Context) {
+        FILE_CAN_OPEN.set(true);
+        let orders = vec![
+            "Build headquarters".to_string(),
+            "Fight enemies".to_string(),
+            "Conquer the world".to_string(),
+        ];
+        let expected_message = concat!(
+            "I, Lex Luthor, as your leader, tell you to:\n",
+            "- Build headquarters\n",
+            "- Fight enemies\n",
+            "- Conquer the world\n"
+        );
+
+        assert_ok_eq_x!(ctx.sut.spread_orders_by_file("some/path", orders), 3);
+        let actual_message = BUF_WRITTEN.take();
+        assert_ok_eq_x!(str::from_utf8(&actual_message), expected_message);
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn spread_orders_by_file_writes_structured_orders(ctx: &mut Context) {
+        FILE_CAN_OPEN.set(true);
+        let orders = [
+            Order::from("Build headquarters"),
+            Order {
+                priority: Priority::High,
+                assignee: Some(7),
+                ..Order::from("Fight enemies")
+            },
+        ];
+        let expected_message = concat!(
+            "I, Lex Luthor, as your leader, tell you to:\n",
+            "- Build headquarters\n",
+            "- Fight enemies [priority: high, assignee: 7]\n"
+        );
+
+        assert_ok_eq_x!(ctx.sut.spread_orders_by_file("some/path", orders), 2);
+        let actual_message = BUF_WRITTEN.take();
+        assert_ok_eq_x!(str::from_utf8(&actual_message), expected_message);
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn read_orders_by_file_returns_io_error_if_missing(ctx: &mut Context) {
+        FILE_CAN_OPEN.set(false);
+        assert_matches!(
+            ctx.sut.read_orders_by_file("some/path"),
+            Err(EvilError::Io {
+                kind: IoErrorKind::NotFound,
+                ..
+            })
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn read_orders_by_file_returns_orders_from_this_supervillain(ctx: &mut Context) {
+        FILE_CAN_OPEN.set(true);
+        BUF_CONTENTS.replace(Vec::from(concat!(
+            "I, Lex Luthor, as your leader, tell you to:\n",
+            "- Build headquarters\n",
+            "- Fight enemies [priority: high, assignee: 7]\n"
+        )));
+
+        let orders = ctx.sut.read_orders_by_file("some/path");
+
+        assert_eq!(
+            orders.ok(),
+            Some(vec![
+                Order::from("Build headquarters"),
+                Order {
+                    priority: Priority::High,
+                    assignee: Some(7),
+                    ..Order::from("Fight enemies")
+                },
+            ])
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn spread_orders_by_file_uses_atomic_overwrite_by_default(ctx: &mut Context) {
+        FILE_CAN_OPEN.set(true);
+
+        assert_ok!(
+            ctx.sut
+                .spread_orders_by_file("some/path", ["Fight enemies"])
+        );
+
+        assert_eq!(WRITE_OPTIONS.take(), Some(WriteOptions::default()));
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn spread_orders_by_file_with_passes_write_options(ctx: &mut Context) {
+        FILE_CAN_OPEN.set(true);
+        let options = WriteOptions {
+            mode: WriteMode::Append,
+            create_dirs: true,
+            ..WriteOptions::default()
+        };
+
+        assert_ok!(
+            ctx.sut
+                .spread_orders_by_file_with("some/path", ["Fight enemies"], options)
+        );
+
+        assert_eq!(WRITE_OPTIONS.take(), Some(options));
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn spread_orders_by_file_appending_to_orders_skips_header(ctx: &mut Context) {
+        FILE_CAN_OPEN.set(true);
+        FILE_HAS_CONTENT.set(true);
+        let options = WriteOptions {
+            mode: WriteMode::Append,
+            ..WriteOptions::default()
+        };
+
+        assert_ok_eq_x!(
+            ctx.sut
+                .spread_orders_by_file_with("some/path", ["Fight enemies"], options),
+            1
+        );
+
+        assert_eq!(BUF_WRITTEN.take(), b"- Fight enemies\n");
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn spread_orders_to_writes_to_any_writer(ctx: &mut Context) {
+        let mut output = vec![];
+
+        assert_ok_eq_x!(
+            ctx.sut
+                .spread_orders_to(&mut output, ["Fight enemies"], OrdersFormat::PlainText),
+            1
+        );
+        assert_eq!(
+            output,
+            b"I, Lex Luthor, as your leader, tell you to:\n- Fight enemies\n"
+        );
+    }
+
+    #[test_context(Context)]
+    #[tokio::test]
+    async fn spread_orders_to_async_writes_to_async_writer(ctx: &mut Context<'_>) {
+        let (mut writer, mut reader) = tokio::io::duplex(1024);
+
+        let written = ctx
+            .sut
+            .spread_orders_to_async(&mut writer, ["Fight enemies"], OrdersFormat::Csv)
+            .await;
+        drop(writer);
+        let mut output = String::new();
+        reader
+            .read_to_string(&mut output)
+            .await
+            .expect("Orders received");
+
+        assert_ok_eq_x!(written, 1);
+        assert_eq!(
+            output,
+            concat!(
+                "leader,order,priority,deadline,assignee,status\n",
+                "Lex Luthor,Fight enemies,normal,,,pending\n"
+            )
+        );
+    }
+
+    #[test_context(Context)]
+    #[tokio::test]
+    async fn async_vulnerable_locations_with_no_file_returns_io_error(ctx: &mut Context<'_>) {
+        FILE_CAN_OPEN.set(false);
+        assert_matches!(
+            ctx.sut.are_there_vulnerable_locations_async().await,
+            Err(EvilError::Io {
+                kind: IoErrorKind::NotFound,
+                ..
+            })
+        );
+    }
+
+    #[test_context(Context)]
+    #[tokio::test]
+    async fn async_vulnerable_locations_with_weak_returns_true(ctx: &mut Context<'_>) {
+        FILE_CAN_OPEN.set(true);
+        BUF_CONTENTS.replace(Vec::from(
+            r"Madrid,strong
+              Las Vegas,weak",
+        ));
+        ctx.sut.listing_path = Some(PathBuf::from("/var/evil/listing.csv"));
+
+        assert_ok_eq_x!(ctx.sut.are_there_vulnerable_locations_async().await, true);
+        assert_eq!(
+            OPENED_PATH.take(),
+            Some(PathBuf::from("/var/evil/listing.csv"))
+        );
+    }
+
+    #[test_context(Context)]
+    #[tokio::test]
+    async fn async_spread_orders_by_file_writes_orders_with_options(ctx: &mut Context<'_>) {
+        FILE_CAN_OPEN.set(true);
+        let options = WriteOptions {
+            mode: WriteMode::Append,
+            ..WriteOptions::default()
+        };
+
+        let written = ctx
+            .sut
+            .spread_orders_by_file_async("some/path", ["Fight enemies"], options)
+            .await;
+
+        assert_ok_eq_x!(written, 1);
+        assert_eq!(WRITE_OPTIONS.take(), Some(options));
+        assert_eq!(
+            BUF_WRITTEN.take(),
+            b"I, Lex Luthor, as your leader, tell you to:\n- Fight enemies\n"
+        );
+    }
+
+    #[test_context(Context)]
+    #[tokio::test]
+    async fn async_spread_orders_by_file_returns_error_on_failure(ctx: &mut Context<'_>) {
+        FILE_CAN_OPEN.set(false);
+
+        let written = ctx
+            .sut
+            .spread_orders_by_file_async("some/path", ["Fight enemies"], WriteOptions::default())
+            .await;
+
+        assert_err!(written);
+    }
+
+    #[tokio::test]
+    async fn async_write_keeps_atomic_overwrite() {
+        let dir = tempfile::tempdir().expect("Temporary directory");
+        let path = dir.path().join("orders.txt");
+
+        let result =
+            aux::open_write_execute_async(path.clone(), WriteOptions::default(), |out, _| {
+                writeln!(out, "New orders")?;
+                Ok(1)
+            })
+            .await;
+
+        assert_ok_eq_x!(result, 1);
+        assert_eq!(
+            fs::read_to_string(&path).ok().as_deref(),
+            Some("New orders\n")
+        );
+    }
+
+    #[test]
+    fn overwrite_keeps_previous_file_if_writing_fails() {
+        let dir = tempfile::tempdir().expect("Temporary directory");
+        let path = dir.path().join("orders.txt");
+        fs::write(&path, "Previous orders\n").expect("Previous orders written");
+
+        let result = aux::open_write_execute(&path, WriteOptions::default(), |buffer, _| {
+            writeln!(buffer, "Half")?;
+            Err(io::Error::other("Interrupted"))
+        });
+
+        assert_err!(result);
+        assert_eq!(
+            fs::read_to_string(&path).ok().as_deref(),
+            Some("Previous orders\n")
+        );
+    }
+
+    #[test]
+    fn overwrite_replaces_previous_file_on_success() {
+        let dir = tempfile::tempdir().expect("Temporary directory");
+        let path = dir.path().join("orders.txt");
+        fs::write(&path, "Previous orders\n").expect("Previous orders written");
+
+        let result = aux::open_write_execute(&path, WriteOptions::default(), |buffer, appended| {
+            assert!(!appended);
+            writeln!(buffer, "New orders")?;
+            Ok(1)
+        });
+
+        assert_ok_eq_x!(result, 1);
+        assert_eq!(
+            fs::read_to_string(&path).ok().as_deref(),
+            Some("New orders\n")
+        );
+    }
+
+    #[test]
+    fn append_keeps_previous_content() {
+        let dir = tempfile::tempdir().expect("Temporary directory");
+        let path = dir.path().join("orders.log");
+        fs::write(&path, "Previous orders\n").expect("Previous orders written");
+        let options = WriteOptions {
+            mode: WriteMode::Append,
+            ..WriteOptions::default()
+        };
+
+        let result = aux::open_write_execute(&path, options, |buffer, appended| {
+            assert!(appended);
+            writeln!(buffer, "New orders")?;
+            Ok(1)
+        });
+
+        assert_ok_eq_x!(result, 1);
+        assert_eq!(
+            fs::read_to_string(&path).ok().as_deref(),
+            Some("Previous orders\nNew orders\n")
+        );
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn orders_appended_twice_are_read_back(ctx: &mut Context) -> Result<(), EvilError> {
+        let dir = tempfile::tempdir().expect("Temporary directory");
+        let path = dir.path().join("orders.log");
+        let options = WriteOptions {
+            mode: WriteMode::Append,
+            ..WriteOptions::default()
+        };
+        let leader = ctx.sut.full_name();
+
+        for order in ["Build headquarters", "Fight enemies"] {
+            assert_ok_eq_x!(
+                aux::open_write_execute(&path, options, |out, has_content| {
+                    write_or_append_orders(out, has_content, options.format, &leader, [order])
+                }),
+                1
+            );
+        }
+
+        let orders = ctx
+            .sut
+            .read_orders_from(aux::open_buf_read(&path).map_err(|err| io_error(&path, &err))?)?;
+        assert_eq!(
+            orders,
+            [
+                Order::from("Build headquarters"),
+                Order::from("Fight enemies")
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn missing_parent_directories_are_only_created_if_requested() {
+        let dir = tempfile::tempdir().expect("Temporary directory");
+        let path = dir.path().join("lair").join("orders").join("today.txt");
+        let options = WriteOptions {
+            create_dirs: true,
+            ..WriteOptions::default()
+        };
+
+        assert_err!(aux::open_write_execute(
+            &path,
+            WriteOptions::default(),
+            |_, _| Ok(0)
+        ));
+        assert_ok!(aux::open_write_execute(&path, options, |_, _| Ok(0)));
+        assert!(path.exists());
+    }
+
+    #[test_context(Context)]
+    #[test]
+    fn spread_orders_by_file_as_writes_requested_format(ctx: &mut Context) {
+        FILE_CAN_OPEN.set(true);
+        let orders = vec!["Fight enemies".to_string(), "Conquer the world".to_string()];
+        let expected_message = concat!(
+            "leader,order,priority,deadline,assignee,status\n",
+            "Lex Luthor,Fight enemies,normal,,,pending\n",
+            "Lex Luthor,Conquer the world,normal,,,pending\n"
+        );
+
+        assert_ok_eq_x!(
+            ctx.sut
+                .spread_orders_by_file_as("some/path", orders, OrdersFormat::Csv),
+            2
+        );
+        let actual_message = BUF_WRITTEN.take();
+        assert_ok_eq_x!(str::from_utf8(&actual_message), expected_message);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test_context(Context)]
+    #[test]
+    fn supervillain_round_trips_through_json_without_sidekick_or_shared_key(ctx: &mut Context) {
+        ctx.sut.shared_key = String::from("Kryptonite");
+        ctx.sut.sidekick = Some(Sidekick::new());
+
+        let json = serde_json::to_value(&ctx.sut).unwrap();
+        let restored: Supervillain = serde_json::from_value(json.clone()).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "first_name": test_common::PRIMARY_FIRST_NAME,
+                "last_name": test_common::PRIMARY_LAST_NAME
+            })
+        );
+        assert_eq!(restored.full_name(), test_common::PRIMARY_FULL_NAME);
+        assert_eq!(restored.shared_key, "");
+        assert_none!(restored.sidekick);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn supervillain_shared_key_can_be_deserialized() {
+        let restored: Supervillain = serde_json::from_value(serde_json::json!({
+            "first_name": test_common::PRIMARY_FIRST_NAME,
+            "last_name": test_common::PRIMARY_LAST_NAME,
+            "shared_key": "Kryptonite"
+        }))
+        .unwrap();
+
+        assert_eq!(restored.shared_key, "Kryptonite");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn evil_error_round_trips_through_json() {
+        let error = EvilError::CipherError {
+            kind: CipherErrorKind::AuthenticationFailed,
+            reason: String::from("Message or key don't match"),
+        };
+
+        let json = serde_json::to_string(&error).unwrap();
+        let restored: EvilError = serde_json::from_str(&json).unwrap();
+
+        assert_matches!(
+            restored,
+            EvilError::CipherError {
+                kind: CipherErrorKind::AuthenticationFailed,
+                reason
+            } if reason == "Message or key don't match"
+        );
+    }
+
+    struct Context<'a> {
+        sut: Supervillain<'a>,
+    }
+
+    impl<'a> AsyncTestContext for Context<'a> {
+        async fn setup() -> Context<'a> {
+            Context {
+                sut: Supervillain {
+                    first_name: test_common::PRIMARY_FIRST_NAME.to_string(),
+                    last_name: test_common::PRIMARY_LAST_NAME.to_string(),
+                    ..Default::default()
+                },
+            }
+        }
+
+        async fn teardown(self) {}
+    }
+
+    pub mod doubles {
+        use std::io::{self, Cursor, Error, ErrorKind};
+
+        use super::*;
+
+        pub struct File {
+            read_result: Option<String>,
+        }
+
+        impl File {
+            pub fn new(read_result: Option<String>) -> File {
+                File { read_result }
+            }
+
+            pub fn open<P: AsRef<Path>>(path: P) -> io::Result<File> {
+                OPENED_PATH.set(Some(path.as_ref().to_path_buf()));
+                if let Some(file) = FILE_IF_CAN_OPEN.take() {
+                    Ok(file)
+                } else {
+                    Err(Error::from(ErrorKind::NotFound))
+                }
+            }
+            pub fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
+                if let Some(ref content) = self.read_result {
+                    *buf = content.to_owned();
+                    Ok(buf.len())
+                } else {
+                    Err(Error::from(ErrorKind::Other))
+                }
+            }
+        }
+
+        pub fn open_buf_read<P: AsRef<Path>>(path: P) -> io::Result<impl BufRead> {
+            OPENED_PATH.set(Some(path.as_ref().to_path_buf()));
+            if FILE_CAN_OPEN.get() {
+                Ok(Cursor::new(BUF_CONTENTS.take()))
+            } else {
+                Err(Error::from(ErrorKind::NotFound))
+            }
+        }
+
+        pub async fn read_all_async(path: &Path) -> io::Result<Vec<u8>> {
+            OPENED_PATH.set(Some(path.to_path_buf()));
+            if FILE_CAN_OPEN.get() {
+                Ok(BUF_CONTENTS.take())
+            } else {
+                Err(Error::from(ErrorKind::NotFound))
+            }
+        }
+
+        pub async fn open_write_execute_async<F>(
+            path: PathBuf,
+            options: WriteOptions,
+            operations: F,
+        ) -> io::Result<usize>
+        where
+            F: FnOnce(&mut dyn Write, bool) -> Result<usize, std::io::Error> + Send + 'static,
+        {
+            open_write_execute(path, options, operations)
         }
 
-        pub fn open_write_execute<P, F>(path: P, operations: F) -> Result<usize, io::Error>
+        pub fn open_write_execute<P, F>(
+            path: P,
+            options: WriteOptions,
+            operations: F,
+        ) -> Result<usize, io::Error>
         where
             P: AsRef<Path>,
-            F: FnOnce(Rc<RefCell<dyn Write>>) -> Result<usize, std::io::Error>,
+            F: FnOnce(&mut dyn Write, bool) -> Result<usize, std::io::Error>,
         {
+            WRITE_OPTIONS.set(Some(options));
             if !FILE_CAN_OPEN.get() {
                 return Err(io::Error::other("Unable to create file"));
             }
-            let mut buffer = Rc::new(RefCell::new(Cursor::new(vec![])));
-            let mut writer = Rc::clone(&buffer) as Rc<RefCell<dyn Write>>;
-            let result = operations(writer);
-            let output = Rc::into_inner(buffer)
-                .expect("Unexpected empty Rc")
-                .take()
-                .into_inner();
+            let mut output = vec![];
+            let result = operations(&mut output, FILE_HAS_CONTENT.take());
             BUF_WRITTEN.set(output);
 
             result
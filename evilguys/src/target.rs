@@ -0,0 +1,201 @@
+//! Module for the strategies used to choose a target among the weak ones
+use std::{cmp::Ordering, sync::Mutex};
+
+#[cfg(test)]
+use mockall::automock;
+use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+use crate::location::{Coordinates, Location};
+
+/// Trait for the strategies that choose a target.
+#[cfg_attr(test, automock)]
+pub trait TargetSelector {
+    /// Returns the chosen target, or `None` if there are no targets to choose from.
+    fn select(&self, targets: &[Location]) -> Option<Location>;
+}
+
+/// Chooses the first target, in the order they were found.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FirstTarget;
+
+impl TargetSelector for FirstTarget {
+    fn select(&self, targets: &[Location]) -> Option<Location> {
+        targets.first().cloned()
+    }
+}
+
+/// Chooses a random target with a seeded generator, so the same seed always makes the same
+/// choices.
+pub struct RandomTarget {
+    rng: Mutex<StdRng>,
+}
+
+impl RandomTarget {
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        RandomTarget {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl TargetSelector for RandomTarget {
+    fn select(&self, targets: &[Location]) -> Option<Location> {
+        let mut rng = self
+            .rng
+            .lock()
+            .expect("Random generator shouldn't be poisoned");
+        targets.choose(&mut *rng).cloned()
+    }
+}
+
+/// Chooses the target with the lowest score in the listing. Targets without a score are only
+/// chosen if none has one, and ties keep the order they were found in.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WeakestTarget;
+
+impl TargetSelector for WeakestTarget {
+    fn select(&self, targets: &[Location]) -> Option<Location> {
+        targets
+            .iter()
+            .min_by_key(|target| target.score.unwrap_or(u32::MAX))
+            .cloned()
+    }
+}
+
+/// Chooses the target closest to an origin. Targets without coordinates are only chosen if none
+/// has them, and ties keep the order they were found in.
+#[derive(Debug, Clone, Copy)]
+pub struct ClosestTarget {
+    pub origin: Coordinates,
+}
+
+impl TargetSelector for ClosestTarget {
+    fn select(&self, targets: &[Location]) -> Option<Location> {
+        let distance = |target: &Location| {
+            target.coordinates.map_or(f64::INFINITY, |coordinates| {
+                self.origin.distance_km(&coordinates)
+            })
+        };
+        targets
+            .iter()
+            .min_by(|a, b| {
+                distance(a)
+                    .partial_cmp(&distance(b))
+                    .unwrap_or(Ordering::Equal)
+            })
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_common;
+
+    fn names(target: Option<Location>) -> Option<String> {
+        target.map(|target| target.name)
+    }
+
+    fn scored(name: &str, score: Option<u32>) -> Location {
+        Location {
+            score,
+            ..Location::from(name)
+        }
+    }
+
+    fn placed(name: &str, latitude: f64, longitude: f64) -> Location {
+        Location {
+            coordinates: Some(Coordinates {
+                latitude,
+                longitude,
+            }),
+            ..Location::from(name)
+        }
+    }
+
+    #[test]
+    fn no_selector_chooses_without_targets() {
+        let origin = Coordinates {
+            latitude: 0.0,
+            longitude: 0.0,
+        };
+        let selectors: [&dyn TargetSelector; 4] = [
+            &FirstTarget,
+            &RandomTarget::new(1),
+            &WeakestTarget,
+            &ClosestTarget { origin },
+        ];
+
+        for selector in selectors {
+            assert_eq!(selector.select(&[]), None);
+        }
+    }
+
+    #[test]
+    fn first_target_is_the_first_one_found() {
+        let targets = test_common::TARGETS.map(Location::from);
+
+        assert_eq!(
+            names(FirstTarget.select(&targets)).as_deref(),
+            Some(test_common::FIRST_TARGET)
+        );
+    }
+
+    #[test]
+    fn random_target_is_reproducible_with_the_same_seed() {
+        let targets = test_common::TARGETS.map(Location::from);
+        let first = RandomTarget::new(42);
+        let second = RandomTarget::new(42);
+
+        for _ in 0..5 {
+            let chosen = first.select(&targets);
+            assert!(chosen.is_some());
+            assert_eq!(chosen, second.select(&targets));
+        }
+    }
+
+    #[test]
+    fn weakest_target_has_the_lowest_score() {
+        let targets = [
+            scored("Tampa", None),
+            scored("Pamplona", Some(7)),
+            scored("Vilnius", Some(3)),
+            scored("Gotham", Some(3)),
+        ];
+
+        assert_eq!(
+            names(WeakestTarget.select(&targets)).as_deref(),
+            Some("Vilnius")
+        );
+    }
+
+    #[test]
+    fn weakest_target_without_scores_is_the_first_one() {
+        let targets = test_common::TARGETS.map(Location::from);
+
+        assert_eq!(
+            names(WeakestTarget.select(&targets)).as_deref(),
+            Some(test_common::FIRST_TARGET)
+        );
+    }
+
+    #[test]
+    fn closest_target_is_nearest_to_origin() {
+        let madrid = Coordinates {
+            latitude: 40.4168,
+            longitude: -3.7038,
+        };
+        let targets = [
+            Location::from("Atlantis"),
+            placed("Tampa", 27.9506, -82.4572),
+            placed("Vilnius", 54.6872, 25.2797),
+            placed("Pamplona", 42.8125, -1.6458),
+        ];
+
+        assert_eq!(
+            names(ClosestTarget { origin: madrid }.select(&targets)).as_deref(),
+            Some("Pamplona")
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use crate::henchman::{Outcome, Task};
+
 pub const PRIMARY_FIRST_NAME: &str = "Lex";
 pub const PRIMARY_LAST_NAME: &str = "Luthor";
 pub const PRIMARY_FULL_NAME: &str = "Lex Luthor";
@@ -8,3 +10,11 @@ pub const FIRST_TARGET: &str = "Tampa";
 pub const TARGETS: [&str; 3] = [FIRST_TARGET, "Pamplona", "Vilnius"];
 pub const MAIN_SECRET_MESSAGE: &str = "Nobody should know this";
 pub const MAIN_CIPHERED_MESSAGE: &str = "+Nobody should know this+";
+
+/// Returns the outcome of the task, as reported by mocked henchmen.
+pub fn outcome(task: Task) -> Outcome {
+    Outcome {
+        task,
+        stamina_left: 0,
+    }
+}
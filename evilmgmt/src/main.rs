@@ -1,14 +1,23 @@
 mod routes;
 
+use std::sync::Arc;
+
+use evil::repository::SqliteSupervillainRepository;
 use tokio::net::TcpListener;
 
 #[tokio::main]
 async fn main() {
     const SERVER_ADDR: &str = "127.0.0.1:8080";
+    const DATABASE_PATH: &str = "evilmgmt.db";
 
+    tracing_subscriber::fmt::init();
+    let repository =
+        SqliteSupervillainRepository::open(DATABASE_PATH).expect("Unable to open database");
     println!("Launching evilmgmt: http://{SERVER_ADDR}");
     let listener = TcpListener::bind(SERVER_ADDR)
         .await
         .expect("Unable to create listener");
-    axum::serve(listener, routes::app()).await.unwrap();
+    axum::serve(listener, routes::app(Arc::new(repository)))
+        .await
+        .unwrap();
 }
@@ -1,31 +1,288 @@
 //! Routes for the HTTP application
+use std::sync::Arc;
+
 use axum::{
-    Router,
+    Json, Router,
+    extract::{FromRequest, Path, State, rejection::JsonRejection},
     http::{StatusCode, Uri},
+    response::{IntoResponse, Response},
     routing::get,
 };
+use evil::{
+    Supervillain,
+    repository::{SupervillainId, SupervillainRepository},
+    supervillain::EvilError,
+};
+use serde::{Deserialize, Serialize};
+
+/// Repository shared by all the handlers.
+pub type SharedRepository = Arc<dyn SupervillainRepository>;
 
-pub fn app() -> Router {
+pub fn app(repository: SharedRepository) -> Router {
     Router::new()
         .route("/", get(|| async { "Evilness Management" }))
+        .route(
+            "/supervillains",
+            get(list_supervillains).post(create_supervillain),
+        )
+        .route(
+            "/supervillains/{id}",
+            get(get_supervillain)
+                .put(replace_supervillain)
+                .patch(modify_supervillain)
+                .delete(delete_supervillain),
+        )
         .fallback(fallback_handler)
+        .with_state(repository)
 }
 
 async fn fallback_handler(uri: Uri) -> (StatusCode, String) {
     (StatusCode::NOT_FOUND, format!("No route for {uri}"))
 }
 
+/// Supervillain as returned by the API. The shared key is never exposed.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+struct SupervillainResponse {
+    id: SupervillainId,
+    first_name: String,
+    last_name: String,
+}
+
+impl SupervillainResponse {
+    fn new(id: SupervillainId, supervillain: &Supervillain) -> Self {
+        SupervillainResponse {
+            id,
+            first_name: supervillain.first_name.clone(),
+            last_name: supervillain.last_name.clone(),
+        }
+    }
+}
+
+/// Body used to create or replace a supervillain.
+#[derive(Debug, Deserialize)]
+struct SupervillainRequest {
+    name: String,
+    #[serde(default)]
+    shared_key: String,
+}
+
+impl SupervillainRequest {
+    fn into_supervillain(self) -> Result<Supervillain<'static>, EvilError> {
+        let mut supervillain = Supervillain::try_from(self.name.as_str())?;
+        supervillain.shared_key = self.shared_key;
+        Ok(supervillain)
+    }
+}
+
+/// Body used to modify some of the attributes of a supervillain.
+#[derive(Debug, Deserialize)]
+struct SupervillainPatch {
+    name: Option<String>,
+    shared_key: Option<String>,
+}
+
+/// Message of the errors that are the server's fault. Their details are only logged.
+const INTERNAL_ERROR: &str = "Internal server error";
+
+/// Body of every error response: a human readable message and, for the errors caused by the
+/// request, the error that caused it.
+#[derive(Debug, Serialize, Deserialize)]
+struct ErrorResponse {
+    error: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    details: Option<EvilError>,
+}
+
+/// Error returned by the handlers, rendered with the status code that matches the `EvilError`.
+struct ApiError(EvilError);
+
+impl From<EvilError> for ApiError {
+    fn from(error: EvilError) -> Self {
+        ApiError(error)
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError(EvilError::ParseError {
+            purpose: String::from("request body"),
+            reason: rejection.body_text(),
+        })
+    }
+}
+
+/// JSON body of a request. Bodies that can't be extracted are rejected with an `ApiError`, like
+/// any other error.
+#[derive(FromRequest)]
+#[from_request(via(Json), rejection(ApiError))]
+struct JsonBody<T>(T);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self.0 {
+            EvilError::ParseError { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            EvilError::NotFound { .. } => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = if status.is_client_error() {
+            ErrorResponse {
+                error: self.0.to_string(),
+                details: Some(self.0),
+            }
+        } else {
+            tracing::error!(error = %self.0, "Request failed");
+            ErrorResponse {
+                error: INTERNAL_ERROR.to_string(),
+                details: None,
+            }
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+/// Runs the repository operation in a thread where blocking is allowed, so slow storage
+/// doesn't stall the runtime.
+async fn with_repository<T, F>(repository: SharedRepository, operation: F) -> Result<T, ApiError>
+where
+    T: Send + 'static,
+    F: FnOnce(&dyn SupervillainRepository) -> Result<T, EvilError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || operation(repository.as_ref()))
+        .await
+        .map_err(|err| EvilError::StorageError {
+            reason: err.to_string(),
+        })?
+        .map_err(ApiError::from)
+}
+
+async fn list_supervillains(
+    State(repository): State<SharedRepository>,
+) -> Result<Json<Vec<SupervillainResponse>>, ApiError> {
+    let supervillains = with_repository(repository, |repository| repository.list())
+        .await?
+        .iter()
+        .map(|(id, supervillain)| SupervillainResponse::new(*id, supervillain))
+        .collect();
+    Ok(Json(supervillains))
+}
+
+async fn create_supervillain(
+    State(repository): State<SharedRepository>,
+    JsonBody(request): JsonBody<SupervillainRequest>,
+) -> Result<(StatusCode, Json<SupervillainResponse>), ApiError> {
+    let supervillain = request.into_supervillain()?;
+    let (id, supervillain) = with_repository(repository, move |repository| {
+        repository
+            .create(&supervillain)
+            .map(|id| (id, supervillain))
+    })
+    .await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(SupervillainResponse::new(id, &supervillain)),
+    ))
+}
+
+async fn get_supervillain(
+    State(repository): State<SharedRepository>,
+    Path(id): Path<SupervillainId>,
+) -> Result<Json<SupervillainResponse>, ApiError> {
+    let supervillain = with_repository(repository, move |repository| repository.get(id)).await?;
+    Ok(Json(SupervillainResponse::new(id, &supervillain)))
+}
+
+async fn replace_supervillain(
+    State(repository): State<SharedRepository>,
+    Path(id): Path<SupervillainId>,
+    JsonBody(request): JsonBody<SupervillainRequest>,
+) -> Result<Json<SupervillainResponse>, ApiError> {
+    let supervillain = request.into_supervillain()?;
+    let supervillain = with_repository(repository, move |repository| {
+        repository.update(id, &supervillain).map(|()| supervillain)
+    })
+    .await?;
+    Ok(Json(SupervillainResponse::new(id, &supervillain)))
+}
+
+async fn modify_supervillain(
+    State(repository): State<SharedRepository>,
+    Path(id): Path<SupervillainId>,
+    JsonBody(patch): JsonBody<SupervillainPatch>,
+) -> Result<Json<SupervillainResponse>, ApiError> {
+    let supervillain = with_repository(repository, move |repository| {
+        repository.modify(id, &mut |supervillain| {
+            if let Some(ref name) = patch.name {
+                let renamed = Supervillain::try_from(name.as_str())?;
+                supervillain.first_name = renamed.first_name;
+                supervillain.last_name = renamed.last_name;
+            }
+            if let Some(ref shared_key) = patch.shared_key {
+                supervillain.shared_key.clone_from(shared_key);
+            }
+            Ok(())
+        })
+    })
+    .await?;
+    Ok(Json(SupervillainResponse::new(id, &supervillain)))
+}
+
+async fn delete_supervillain(
+    State(repository): State<SharedRepository>,
+    Path(id): Path<SupervillainId>,
+) -> Result<StatusCode, ApiError> {
+    with_repository(repository, move |repository| repository.delete(id)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 #[cfg(test)]
 mod tests {
-    use axum::{body::Body, extract::Request};
+    use axum::{body::Body, extract::Request, http::Method};
+    use evil::repository::InMemorySupervillainRepository;
     use http_body_util::BodyExt;
+    use serde::de::DeserializeOwned;
     use tower::ServiceExt;
 
     use super::*;
 
+    fn repository_with(names: &[&str]) -> SharedRepository {
+        let repository = InMemorySupervillainRepository::default();
+        for name in names {
+            repository
+                .create(&Supervillain::try_from(*name).unwrap())
+                .unwrap();
+        }
+        Arc::new(repository)
+    }
+
+    fn json_request(method: Method, uri: &str, body: &str) -> Request {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    async fn json_body<T: DeserializeOwned>(response: Response) -> T {
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn supervillain_response(
+        id: SupervillainId,
+        first_name: &str,
+        last_name: &str,
+    ) -> SupervillainResponse {
+        SupervillainResponse {
+            id,
+            first_name: first_name.to_string(),
+            last_name: last_name.to_string(),
+        }
+    }
+
     #[tokio::test]
     async fn root_return_static_response_and_ok() {
-        let routes = app();
+        let routes = app(repository_with(&[]));
         let request = Request::builder().uri("/").body(Body::empty()).unwrap();
 
         let response = routes.oneshot(request).await.unwrap();
@@ -37,7 +294,7 @@ mod tests {
 
     #[tokio::test]
     async fn nonexisting_url_returns_emply_response_and_not_found() {
-        let routes = app();
+        let routes = app(repository_with(&[]));
         let request = Request::builder()
             .uri("/nonexisting")
             .body(Body::empty())
@@ -49,4 +306,257 @@ mod tests {
         let body = response.into_body().collect().await.unwrap().to_bytes();
         assert_eq!(&body, "No route for /nonexisting");
     }
+
+    #[tokio::test]
+    async fn list_supervillains_returns_all_stored_and_ok() {
+        let routes = app(repository_with(&["Lex Luthor", "Darth Vader"]));
+        let request = Request::builder()
+            .uri("/supervillains")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = routes.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Vec<SupervillainResponse> = json_body(response).await;
+        assert_eq!(
+            body,
+            vec![
+                supervillain_response(1, "Lex", "Luthor"),
+                supervillain_response(2, "Darth", "Vader")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn create_supervillain_stores_it_and_returns_created() {
+        let repository = repository_with(&[]);
+        let routes = app(Arc::clone(&repository));
+        let request = json_request(
+            Method::POST,
+            "/supervillains",
+            r#"{"name": "Lex Luthor", "shared_key": "Kryptonite"}"#,
+        );
+
+        let response = routes.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body: SupervillainResponse = json_body(response).await;
+        assert_eq!(body, supervillain_response(1, "Lex", "Luthor"));
+        assert_eq!(repository.get(1).unwrap().shared_key, "Kryptonite");
+    }
+
+    #[tokio::test]
+    async fn create_supervillain_with_invalid_name_returns_unprocessable_entity() {
+        let repository = repository_with(&[]);
+        let routes = app(Arc::clone(&repository));
+        let request = json_request(Method::POST, "/supervillains", r#"{"name": "Lex"}"#);
+
+        let response = routes.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: ErrorResponse = json_body(response).await;
+        assert_eq!(
+            body.error,
+            "Parse error: purpose='full_name', reason='Too few arguments'"
+        );
+        assert!(matches!(
+            body.details,
+            Some(EvilError::ParseError { purpose, .. }) if purpose == "full_name"
+        ));
+        assert!(repository.list().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn bad_bodies_return_error_response() {
+        for (method, uri, body) in [
+            (Method::POST, "/supervillains", r#"{"name": "#),
+            (
+                Method::PUT,
+                "/supervillains/1",
+                r#"{"shared_key": "Kryptonite"}"#,
+            ),
+            (Method::PATCH, "/supervillains/1", r#"{"name": 47}"#),
+        ] {
+            let repository = repository_with(&["Lex Luthor"]);
+            let routes = app(Arc::clone(&repository));
+
+            let response = routes
+                .oneshot(json_request(method, uri, body))
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+            let body: ErrorResponse = json_body(response).await;
+            assert!(matches!(
+                body.details,
+                Some(EvilError::ParseError { purpose, .. }) if purpose == "request body"
+            ));
+            assert_eq!(repository.get(1).unwrap().full_name(), "Lex Luthor");
+            assert_eq!(repository.list().unwrap().len(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn server_errors_hide_their_details() {
+        let response = ApiError(EvilError::StorageError {
+            reason: String::from("disk I/O error at /var/lib/evil.db"),
+        })
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            serde_json::json!({ "error": INTERNAL_ERROR })
+        );
+    }
+
+    #[tokio::test]
+    async fn get_supervillain_never_exposes_shared_key() {
+        let repository = repository_with(&[]);
+        let mut supervillain = Supervillain::try_from("Lex Luthor").unwrap();
+        supervillain.shared_key = String::from("Kryptonite");
+        repository.create(&supervillain).unwrap();
+        let routes = app(repository);
+        let request = Request::builder()
+            .uri("/supervillains/1")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = routes.oneshot(request).await.unwrap();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(!str::from_utf8(&body).unwrap().contains("Kryptonite"));
+    }
+
+    #[tokio::test]
+    async fn get_supervillain_returns_it_and_ok() {
+        let routes = app(repository_with(&["Lex Luthor", "Darth Vader"]));
+        let request = Request::builder()
+            .uri("/supervillains/2")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = routes.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: SupervillainResponse = json_body(response).await;
+        assert_eq!(body, supervillain_response(2, "Darth", "Vader"));
+    }
+
+    #[tokio::test]
+    async fn get_unknown_supervillain_returns_not_found() {
+        let routes = app(repository_with(&[]));
+        let request = Request::builder()
+            .uri("/supervillains/7")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = routes.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body: ErrorResponse = json_body(response).await;
+        assert_eq!(body.error, "Not found: entity='supervillain', id=7");
+        assert!(matches!(
+            body.details,
+            Some(EvilError::NotFound { id: 7, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn replace_supervillain_updates_it_and_returns_ok() {
+        let repository = repository_with(&["Lex Luthor"]);
+        let routes = app(Arc::clone(&repository));
+        let request = json_request(
+            Method::PUT,
+            "/supervillains/1",
+            r#"{"name": "Darth Vader"}"#,
+        );
+
+        let response = routes.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: SupervillainResponse = json_body(response).await;
+        assert_eq!(body, supervillain_response(1, "Darth", "Vader"));
+        assert_eq!(repository.get(1).unwrap().full_name(), "Darth Vader");
+    }
+
+    #[tokio::test]
+    async fn replace_supervillain_with_invalid_name_returns_unprocessable_entity() {
+        let repository = repository_with(&["Lex Luthor"]);
+        let routes = app(Arc::clone(&repository));
+        let request = json_request(Method::PUT, "/supervillains/1", r#"{"name": ""}"#);
+
+        let response = routes.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(repository.get(1).unwrap().full_name(), "Lex Luthor");
+    }
+
+    #[tokio::test]
+    async fn modify_supervillain_only_changes_provided_attributes() {
+        let repository = repository_with(&["Lex Luthor"]);
+        let routes = app(Arc::clone(&repository));
+        let request = json_request(
+            Method::PATCH,
+            "/supervillains/1",
+            r#"{"shared_key": "Kryptonite"}"#,
+        );
+
+        let response = routes.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: SupervillainResponse = json_body(response).await;
+        assert_eq!(body, supervillain_response(1, "Lex", "Luthor"));
+        assert_eq!(repository.get(1).unwrap().shared_key, "Kryptonite");
+    }
+
+    #[tokio::test]
+    async fn modify_supervillain_with_invalid_name_keeps_it() {
+        let repository = repository_with(&["Lex Luthor"]);
+        let routes = app(Arc::clone(&repository));
+        let request = json_request(
+            Method::PATCH,
+            "/supervillains/1",
+            r#"{"name": "Lex", "shared_key": "Kryptonite"}"#,
+        );
+
+        let response = routes.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let stored = repository.get(1).unwrap();
+        assert_eq!(stored.full_name(), "Lex Luthor");
+        assert_eq!(stored.shared_key, "");
+    }
+
+    #[tokio::test]
+    async fn modify_unknown_supervillain_returns_not_found() {
+        let routes = app(repository_with(&[]));
+        let request = json_request(
+            Method::PATCH,
+            "/supervillains/3",
+            r#"{"name": "Darth Vader"}"#,
+        );
+
+        let response = routes.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn delete_supervillain_removes_it_and_returns_no_content() {
+        let repository = repository_with(&["Lex Luthor"]);
+        let routes = app(Arc::clone(&repository));
+        let request = Request::builder()
+            .method(Method::DELETE)
+            .uri("/supervillains/1")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = routes.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(repository.list().unwrap().is_empty());
+    }
 }